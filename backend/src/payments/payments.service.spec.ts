@@ -8,6 +8,7 @@ import { Payment } from './entities/payment.entity';
 import { PaymentStatus } from '../common/enums/payment-status.enum';
 import { Shipment } from '../shipments/entities/shipment.entity';
 import { ShipmentStatus } from '../common/enums/shipment-status.enum';
+import { ShipmentEvent } from '../shipments/events/shipment.events';
 import { User } from '../users/entities/user.entity';
 import { StellarContractService } from '../stellar/stellar-contract.service';
 import {
@@ -40,6 +41,8 @@ const mockStellarContractService = () => ({
   buildFundEscrowTransaction: jest.fn(),
   submitSignedTransaction: jest.fn(),
   fundEscrow: jest.fn(),
+  cancelEscrow: jest.fn(),
+  isEnabled: jest.fn(() => true),
 });
 const mockConfigService = (values: Record<string, string> = {}) => ({
   get: jest.fn((key: string): string | undefined => values[key]),
@@ -467,4 +470,55 @@ describe('PaymentsService', () => {
       );
     });
   });
+
+  describe('onShipmentCancelled', () => {
+    const cancelled = () => new ShipmentEvent(makeShipment(), 'shipper-1');
+
+    it('cancels a funded escrow on-chain and marks the payment cancelled', async () => {
+      paymentRepo.findOne.mockResolvedValue(
+        makePayment({ status: PaymentStatus.FUNDED }),
+      );
+      stellarContractService.cancelEscrow.mockResolvedValue({
+        txHash: 'cancel-tx-hash',
+        status: 'SUCCESS',
+      });
+
+      await service.onShipmentCancelled(cancelled());
+
+      expect(stellarContractService.cancelEscrow).toHaveBeenCalledWith(1n);
+      expect(paymentRepo.update).toHaveBeenCalledWith(
+        'payment-1',
+        expect.objectContaining({
+          status: PaymentStatus.CANCELLED,
+          stellarTxHash: 'cancel-tx-hash',
+        }),
+      );
+    });
+
+    it('leaves unfunded payments alone', async () => {
+      paymentRepo.findOne.mockResolvedValue(makePayment());
+
+      await service.onShipmentCancelled(cancelled());
+
+      expect(stellarContractService.cancelEscrow).not.toHaveBeenCalled();
+      expect(paymentRepo.update).not.toHaveBeenCalled();
+    });
+
+    it('records a chain failure without throwing', async () => {
+      paymentRepo.findOne.mockResolvedValue(
+        makePayment({ status: PaymentStatus.FUNDED }),
+      );
+      stellarContractService.cancelEscrow.mockRejectedValue(
+        new EscrowContractError(EscrowErrorCode.Paused, 'paused'),
+      );
+
+      await expect(
+        service.onShipmentCancelled(cancelled()),
+      ).resolves.toBeUndefined();
+
+      expect(paymentRepo.update).toHaveBeenCalledWith('payment-1', {
+        failureReason: expect.any(String),
+      });
+    });
+  });
 });
@@ -6,6 +6,7 @@ import {
   NotFoundException,
 } from '@nestjs/common';
 import { InjectRepository } from '@nestjs/typeorm';
+import { OnEvent } from '@nestjs/event-emitter';
 import { ConfigService } from '@nestjs/config';
 import { Repository } from 'typeorm';
 import { Keypair } from '@stellar/stellar-sdk';
@@ -13,6 +14,10 @@ import { Payment } from './entities/payment.entity';
 import { PaymentStatus } from '../common/enums/payment-status.enum';
 import { Shipment } from '../shipments/entities/shipment.entity';
 import { ShipmentStatus } from '../common/enums/shipment-status.enum';
+import {
+  SHIPMENT_CANCELLED,
+  ShipmentEvent,
+} from '../shipments/events/shipment.events';
 import { User } from '../users/entities/user.entity';
 import { StellarContractService } from '../stellar/stellar-contract.service';
 import { ContractCallResult } from '../stellar/escrow-record.interface';
@@ -251,6 +256,46 @@ export class PaymentsService {
     );
   }
 
+  // ── Cancellation ─────────────────────────────────────────────────────────
+
+  /**
+   * Settles the escrow of a cancelled shipment with `cancel_escrow`, which
+   * refunds the shipper minus the cancellation fee for the stage the
+   * shipment reached. Only a FUNDED payment holds funds on-chain. A chain
+   * failure is logged and recorded on the payment rather than thrown, so it
+   * never undoes the cancellation itself; the payment stays FUNDED for a
+   * retry.
+   */
+  @OnEvent(SHIPMENT_CANCELLED)
+  async onShipmentCancelled({ shipment }: ShipmentEvent): Promise<void> {
+    if (!this.stellarContractService.isEnabled()) return;
+
+    const payment = await this.paymentRepo.findOne({
+      where: { shipmentId: shipment.id },
+    });
+    if (!payment || payment.status !== PaymentStatus.FUNDED) return;
+
+    try {
+      const result = await this.stellarContractService.cancelEscrow(
+        BigInt(payment.onChainShipmentId),
+      );
+      await this.paymentRepo.update(payment.id, {
+        status: PaymentStatus.CANCELLED,
+        settledAt: new Date(),
+        stellarTxHash: result.txHash,
+        failureReason: null,
+      });
+    } catch (error) {
+      const mapped = mapStellarFundingError(error);
+      this.logger.error(
+        `cancel_escrow failed for payment ${payment.id}: ${mapped.message}`,
+      );
+      await this.paymentRepo.update(payment.id, {
+        failureReason: mapped.code,
+      });
+    }
+  }
+
   private async getFundablePayment(
     shipmentId: string,
     paymentId: string,
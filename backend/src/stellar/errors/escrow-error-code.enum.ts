@@ -14,6 +14,47 @@ export enum EscrowErrorCode {
   Unauthorized = 7,
   InvalidAmount = 8,
   InsufficientBalance = 9,
+  MultisigRequired = 10,
+  NotSigner = 11,
+  AlreadyApproved = 12,
+  ProposalNotFound = 13,
+  ProposalExecuted = 14,
+  ThresholdNotMet = 15,
+  InvalidThreshold = 16,
+  TimelockActive = 17,
+  Paused = 18,
+  InvalidMilestones = 19,
+  MilestoneNotFound = 20,
+  MilestoneAlreadyReleased = 21,
+  InvalidRuling = 22,
+  TokenNotAllowed = 23,
+  InvalidCommission = 24,
+  InvalidFeeTable = 25,
+  ShipmentContractNotSet = 26,
+  ShipmentNotSettled = 27,
+  PartyMismatch = 28,
+  AutoReleaseDisabled = 29,
+  DeadlineNotReached = 30,
+  NoDeadlineSet = 31,
+  NoPendingAdmin = 32,
+  AdminTransferExpired = 33,
+  NoExcess = 34,
+  SettlementFinal = 35,
+  InvalidBatch = 36,
+  MigrationPending = 37,
+  InsufficientBond = 38,
+  BondLocked = 39,
+  NoBondPosted = 40,
+  NoWithdrawalPending = 41,
+  NotAssigned = 42,
+  NotStreaming = 43,
+  StreamNotStarted = 44,
+  NothingVested = 45,
+  LegNotFound = 46,
+  LegAlreadyPaid = 47,
+  LegNotConfirmed = 48,
+  PaidPerLeg = 49,
+  LegUnassigned = 50,
 }
 
 export function escrowErrorCodeName(code: number): string {
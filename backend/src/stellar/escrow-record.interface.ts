@@ -1,10 +1,16 @@
+import { EscrowErrorCode } from './errors/escrow-error-code.enum';
+
 /** Mirrors `EscrowStatus` in contracts/escrow/src/lib.rs. */
 export type EscrowStatus =
   | 'Pending'
   | 'Funded'
   | 'Released'
   | 'Refunded'
-  | 'Disputed';
+  | 'Disputed'
+  // Release or refund waiting out the contract's challenge window.
+  | 'Scheduled'
+  // Dispute ruled with the funds split between carrier and shipper.
+  | 'Split';
 
 /** Mirrors `EscrowRecord` in contracts/escrow/src/lib.rs. */
 export interface EscrowRecord {
@@ -22,3 +28,25 @@ export interface ContractCallResult {
   txHash: string;
   status: string;
 }
+
+/**
+ * Mirrors `BatchOutcome` in contracts/escrow/src/lib.rs: what a batch
+ * settlement did with one shipment.
+ */
+export type BatchOutcome =
+  | { kind: 'Settled' }
+  // A settlement delay is configured; the release or refund is pending.
+  | { kind: 'Scheduled' }
+  // Skipped with this contract error; nothing changed for the shipment.
+  | { kind: 'Failed'; code: EscrowErrorCode };
+
+/** Mirrors `BatchResult` in contracts/escrow/src/lib.rs. */
+export interface BatchResult {
+  shipmentId: bigint;
+  outcome: BatchOutcome;
+}
+
+/** A submitted batch call plus the per-shipment outcomes it reported. */
+export interface BatchCallResult extends ContractCallResult {
+  results: BatchResult[];
+}
@@ -18,7 +18,10 @@ import {
   SimulationError,
   SubmissionError,
 } from './errors/stellar-integration.errors';
-import { EscrowErrorCode } from './errors/escrow-error-code.enum';
+import {
+  EscrowErrorCode,
+  escrowErrorCodeName,
+} from './errors/escrow-error-code.enum';
 
 const mockGetAccount = jest.fn();
 const mockSimulateTransaction = jest.fn();
@@ -112,6 +115,40 @@ function stubAssembleAndSend(sendResponse: { status: string; hash: string }) {
   mockSendTransaction.mockResolvedValueOnce(sendResponse);
 }
 
+function escrowRecordScVal(status: xdr.ScVal): xdr.ScVal {
+  const entries = [
+    ['shipment_id', nativeToScVal(42n, { type: 'u64' })],
+    ['shipper', new Address(shipperKeypair.publicKey()).toScVal()],
+    ['carrier', new Address(carrierKeypair.publicKey()).toScVal()],
+    ['amount', nativeToScVal(500_000_000n, { type: 'i128' })],
+    ['status', status],
+    ['funded_at', nativeToScVal(1_700_000_000n, { type: 'u64' })],
+    ['settled_at', nativeToScVal(0n, { type: 'u64' })],
+  ] as const;
+  return xdr.ScVal.scvMap(
+    entries.map(
+      ([key, val]) =>
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol(key),
+          val,
+        }),
+    ),
+  );
+}
+
+function batchResultScVal(shipmentId: bigint, outcome: xdr.ScVal[]) {
+  return xdr.ScVal.scvMap([
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('outcome'),
+      val: xdr.ScVal.scvVec(outcome),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('shipment_id'),
+      val: nativeToScVal(shipmentId, { type: 'u64' }),
+    }),
+  ]);
+}
+
 async function readyService(
   overrides: Record<string, string> = {},
 ): Promise<StellarContractService> {
@@ -230,6 +267,40 @@ describe('StellarContractService', () => {
       expect(mockGetAccount).toHaveBeenCalledWith(adminKeypair.publicKey());
     });
 
+    it('releaseBatch submits every shipment id in one admin call', async () => {
+      const service = await readyService();
+
+      mockSimulateTransaction.mockResolvedValueOnce(
+        successSim(
+          xdr.ScVal.scvVec([
+            batchResultScVal(1n, [xdr.ScVal.scvSymbol('Settled')]),
+            batchResultScVal(2n, [xdr.ScVal.scvSymbol('Scheduled')]),
+            batchResultScVal(3n, [
+              xdr.ScVal.scvSymbol('Failed'),
+              nativeToScVal(6, { type: 'u32' }),
+            ]),
+          ]),
+        ),
+      );
+      stubAssembleAndSend({ status: 'PENDING', hash: 'batch-hash' });
+
+      const result = await service.releaseBatch([1n, 2n, 3n]);
+
+      expect(result).toEqual({
+        txHash: 'batch-hash',
+        status: 'PENDING',
+        results: [
+          { shipmentId: 1n, outcome: { kind: 'Settled' } },
+          { shipmentId: 2n, outcome: { kind: 'Scheduled' } },
+          {
+            shipmentId: 3n,
+            outcome: { kind: 'Failed', code: EscrowErrorCode.InvalidStatus },
+          },
+        ],
+      });
+      expect(mockGetAccount).toHaveBeenCalledWith(adminKeypair.publicKey());
+    });
+
     it('parses a contract-level rejection into a typed EscrowContractError', async () => {
       const service = await readyService();
 
@@ -251,6 +322,19 @@ describe('StellarContractService', () => {
       expect(error.code).toBe(EscrowErrorCode.InvalidStatus);
     });
 
+    it('maps error codes added after launch, e.g. MultisigRequired', async () => {
+      const service = await readyService();
+
+      mockSimulateTransaction.mockResolvedValueOnce(
+        errorSim('HostError: Error(Contract, #10)'),
+      );
+      const error = (await service
+        .releasePayment(42n)
+        .catch((e: unknown) => e)) as EscrowContractError;
+      expect(error.code).toBe(EscrowErrorCode.MultisigRequired);
+      expect(escrowErrorCodeName(error.code)).toBe('MultisigRequired');
+    });
+
     it('falls back to a generic SimulationError when the error string does not match the contract-error pattern', async () => {
       const service = await readyService();
 
@@ -392,27 +476,14 @@ describe('StellarContractService', () => {
     it('decodes a full EscrowRecord from getEscrow', async () => {
       const service = await readyService();
 
-      const entries = [
-        ['shipment_id', nativeToScVal(42n, { type: 'u64' })],
-        ['shipper', new Address(shipperKeypair.publicKey()).toScVal()],
-        ['carrier', new Address(carrierKeypair.publicKey()).toScVal()],
-        ['amount', nativeToScVal(500_000_000n, { type: 'i128' })],
-        ['status', xdr.ScVal.scvVec([xdr.ScVal.scvSymbol('Funded')])],
-        ['funded_at', nativeToScVal(1_700_000_000n, { type: 'u64' })],
-        ['settled_at', nativeToScVal(0n, { type: 'u64' })],
-      ] as const;
-      const recordScVal = xdr.ScVal.scvMap(
-        entries.map(
-          ([key, val]) =>
-            new xdr.ScMapEntry({
-              key: xdr.ScVal.scvSymbol(key),
-              val,
-            }),
+      mockSimulateTransaction.mockResolvedValueOnce(
+        successSim(
+          escrowRecordScVal(
+            xdr.ScVal.scvVec([xdr.ScVal.scvSymbol('Funded')]),
+          ),
         ),
       );
 
-      mockSimulateTransaction.mockResolvedValueOnce(successSim(recordScVal));
-
       const record = await service.getEscrow(42n);
 
       expect(record).toEqual({
@@ -426,6 +497,37 @@ describe('StellarContractService', () => {
       });
     });
 
+    it('decodes a Scheduled status without its settlement kind', async () => {
+      const service = await readyService();
+
+      mockSimulateTransaction.mockResolvedValueOnce(
+        successSim(
+          escrowRecordScVal(
+            xdr.ScVal.scvVec([
+              xdr.ScVal.scvSymbol('Scheduled'),
+              xdr.ScVal.scvVec([xdr.ScVal.scvSymbol('Release')]),
+            ]),
+          ),
+        ),
+      );
+
+      const record = await service.getEscrow(42n);
+      expect(record.status).toBe('Scheduled');
+    });
+
+    it('decodes a Split status from a split dispute ruling', async () => {
+      const service = await readyService();
+
+      mockSimulateTransaction.mockResolvedValueOnce(
+        successSim(
+          escrowRecordScVal(xdr.ScVal.scvVec([xdr.ScVal.scvSymbol('Split')])),
+        ),
+      );
+
+      const record = await service.getEscrow(42n);
+      expect(record.status).toBe('Split');
+    });
+
     it('decodes a bigint balance from getBalance', async () => {
       const service = await readyService();
 
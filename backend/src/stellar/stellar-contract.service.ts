@@ -19,7 +19,12 @@ import {
   SubmissionError,
 } from './errors/stellar-integration.errors';
 import { EscrowErrorCode } from './errors/escrow-error-code.enum';
-import { ContractCallResult, EscrowRecord } from './escrow-record.interface';
+import {
+  BatchCallResult,
+  BatchResult,
+  ContractCallResult,
+  EscrowRecord,
+} from './escrow-record.interface';
 
 const TX_TIMEOUT_SECONDS = 30;
 
@@ -47,6 +52,7 @@ export class StellarContractService implements OnModuleInit {
   private server: SorobanRpc.Server;
   private contract: Contract;
   private adminKeypair: Keypair;
+  private tokenAddress: string;
   private networkPassphrase: string;
   private enabled = false;
 
@@ -69,15 +75,16 @@ export class StellarContractService implements OnModuleInit {
     const rpcUrl = this.requireEnv('SOROBAN_RPC_URL');
     const networkPassphrase = this.requireEnv('STELLAR_NETWORK_PASSPHRASE');
     const escrowAddress = this.requireEnv('ESCROW_CONTRACT_ADDRESS');
-    // Validated even though this service doesn't reference it directly —
-    // fail fast here rather than mid-flow in whatever later issue reads it.
-    this.requireEnv('TOKEN_CONTRACT_ADDRESS');
+    // Settlement token passed to fund_escrow; must be on the contract's
+    // token allowlist.
+    const tokenAddress = this.requireEnv('TOKEN_CONTRACT_ADDRESS');
     const adminSecret = this.requireEnv('PLATFORM_ADMIN_SECRET');
 
     this.networkPassphrase = networkPassphrase;
     this.server = new SorobanRpc.Server(rpcUrl);
     this.contract = new Contract(escrowAddress);
     this.adminKeypair = Keypair.fromSecret(adminSecret);
+    this.tokenAddress = tokenAddress;
 
     await this.assertAdminKeyMatchesContract();
 
@@ -105,6 +112,7 @@ export class StellarContractService implements OnModuleInit {
       new Address(signer.publicKey()).toScVal(),
       new Address(carrier).toScVal(),
       nativeToScVal(shipmentId, { type: 'u64' }),
+      new Address(this.tokenAddress).toScVal(),
       nativeToScVal(amount, { type: 'i128' }),
     );
   }
@@ -134,6 +142,7 @@ export class StellarContractService implements OnModuleInit {
           new Address(shipperPublicKey).toScVal(),
           new Address(carrierAddress).toScVal(),
           nativeToScVal(shipmentId, { type: 'u64' }),
+          new Address(this.tokenAddress).toScVal(),
           nativeToScVal(amount, { type: 'i128' }),
         ),
       )
@@ -205,6 +214,35 @@ export class StellarContractService implements OnModuleInit {
     );
   }
 
+  /**
+   * Cancels a funded escrow: the payer is refunded minus the cancellation
+   * fee for the stage the linked shipment reached, which goes to the carrier.
+   */
+  async cancelEscrow(shipmentId: bigint): Promise<ContractCallResult> {
+    this.assertEnabled();
+    return this.invoke(
+      this.adminKeypair,
+      'cancel_escrow',
+      nativeToScVal(shipmentId, { type: 'u64' }),
+    );
+  }
+
+  /**
+   * End-of-day settlement in one transaction. The contract settles every
+   * eligible shipment and reports the rest per ID instead of reverting; at
+   * most MAX_BATCH_SIZE (25) IDs per call. `results` are the outcomes the
+   * simulation predicted for the submitted transaction.
+   */
+  async releaseBatch(shipmentIds: bigint[]): Promise<BatchCallResult> {
+    this.assertEnabled();
+    return this.invokeBatch('release_batch', shipmentIds);
+  }
+
+  async refundBatch(shipmentIds: bigint[]): Promise<BatchCallResult> {
+    this.assertEnabled();
+    return this.invokeBatch('refund_batch', shipmentIds);
+  }
+
   async resolveDispute(
     shipmentId: bigint,
     releaseToCarrier: boolean,
@@ -229,7 +267,10 @@ export class StellarContractService implements OnModuleInit {
   }
 
   async getBalance(): Promise<bigint> {
-    const retval = await this.simulateRead('get_balance');
+    const retval = await this.simulateRead(
+      'get_balance',
+      new Address(this.tokenAddress).toScVal(),
+    );
     return BigInt(scValToNative(retval) as bigint | number);
   }
 
@@ -292,6 +333,31 @@ export class StellarContractService implements OnModuleInit {
     method: string,
     ...args: xdr.ScVal[]
   ): Promise<ContractCallResult> {
+    const { result } = await this.invokeReturning(signer, method, ...args);
+    return result;
+  }
+
+  private async invokeBatch(
+    method: string,
+    shipmentIds: bigint[],
+  ): Promise<BatchCallResult> {
+    const { result, retval } = await this.invokeReturning(
+      this.adminKeypair,
+      method,
+      StellarContractService.u64Vec(shipmentIds),
+    );
+    return {
+      ...result,
+      results: StellarContractService.decodeBatchResults(retval),
+    };
+  }
+
+  /** Like `invoke`, but also hands back the simulated return value. */
+  private async invokeReturning(
+    signer: Keypair,
+    method: string,
+    ...args: xdr.ScVal[]
+  ): Promise<{ result: ContractCallResult; retval: xdr.ScVal }> {
     this.assertEnabled();
     const sourceAccount = await this.server.getAccount(signer.publicKey());
 
@@ -305,6 +371,13 @@ export class StellarContractService implements OnModuleInit {
 
     const sim = await this.server.simulateTransaction(rawTx);
     this.throwIfSimulationFailed(sim, method);
+    if (!SorobanRpc.Api.isSimulationSuccess(sim) || sim.result === undefined) {
+      throw new SimulationError(
+        `Simulating "${method}" returned no result`,
+        JSON.stringify(sim),
+      );
+    }
+    const retval = sim.result.retval;
 
     let prepared;
     try {
@@ -318,7 +391,8 @@ export class StellarContractService implements OnModuleInit {
 
     prepared.sign(signer);
 
-    return this.submitPrepared(prepared, method);
+    const result = await this.submitPrepared(prepared, method);
+    return { result, retval };
   }
 
   /** Shared submit + status-mapping tail for both self-signed and externally-signed calls. */
@@ -379,6 +453,12 @@ export class StellarContractService implements OnModuleInit {
     );
   }
 
+  private static u64Vec(values: bigint[]): xdr.ScVal {
+    return xdr.ScVal.scvVec(
+      values.map((value) => nativeToScVal(value, { type: 'u64' })),
+    );
+  }
+
   private static decodeEscrowRecord(retval: xdr.ScVal): EscrowRecord {
     const native = scValToNative(retval) as Record<string, unknown>;
     return {
@@ -392,6 +472,31 @@ export class StellarContractService implements OnModuleInit {
     };
   }
 
+  private static decodeBatchResults(retval: xdr.ScVal): BatchResult[] {
+    const native = scValToNative(retval) as Record<string, unknown>[];
+    return native.map((entry) => ({
+      shipmentId: BigInt(entry.shipment_id as bigint | number),
+      outcome: StellarContractService.decodeBatchOutcome(entry.outcome),
+    }));
+  }
+
+  private static decodeBatchOutcome(raw: unknown): BatchResult['outcome'] {
+    // Encoded like EscrowStatus (see decodeStatus); Failed carries the
+    // EscrowError code as its second element.
+    const [tag, code] = Array.isArray(raw) ? (raw as unknown[]) : [raw];
+    switch (tag) {
+      case 'Settled':
+      case 'Scheduled':
+        return { kind: tag };
+      case 'Failed':
+        return { kind: 'Failed', code: Number(code) as EscrowErrorCode };
+      default:
+        throw new Error(
+          `Unrecognized BatchOutcome value: ${JSON.stringify(raw)}`,
+        );
+    }
+  }
+
   private static decodeStatus(raw: unknown): EscrowRecord['status'] {
     // #[contracttype] enums always encode as ScVal::Vec([Symbol(tag), ...
     // values]), even for unit variants with no associated data — so
@@ -399,6 +504,8 @@ export class StellarContractService implements OnModuleInit {
     // ["Funded"], not a bare string.
     // Array.isArray narrows to any[], not unknown[] — cast back explicitly
     // so the switch below type-checks against unknown, not any.
+    // Scheduled(SettlementKind) carries its kind as a second element; only
+    // the tag is surfaced.
     const tag: unknown = Array.isArray(raw) ? (raw[0] as unknown) : raw;
     switch (tag) {
       case 'Pending':
@@ -406,6 +513,8 @@ export class StellarContractService implements OnModuleInit {
       case 'Released':
       case 'Refunded':
       case 'Disputed':
+      case 'Scheduled':
+      case 'Split':
         return tag;
       default:
         throw new Error(
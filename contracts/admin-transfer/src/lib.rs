@@ -0,0 +1,320 @@
+#![no_std]
+
+//! Two-step admin rotation shared by every contract in the workspace.
+//!
+//! The current admin nominates a successor, who must accept within
+//! `ADMIN_TRANSFER_WINDOW`; the admin can withdraw the nomination until then.
+//! Each contract keeps its own storage keys, error enum and event namespace
+//! and describes them with an `AdminTransfer`.
+
+use soroban_sdk::{contracttype, symbol_short, Address, Env, IntoVal, Symbol, Val};
+
+/// How long a proposed admin has to accept.
+pub const ADMIN_TRANSFER_WINDOW: u64 = 7 * 24 * 60 * 60;
+
+/// Admin transfer awaiting the new admin's signature.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingAdmin {
+    pub admin: Address,
+    pub expires_at: u64,
+}
+
+// Events: topics are `(<namespace>, <name>, <event_version>)`.
+//
+//   admin_new  AdminProposedEvent
+//   admin_set  AdminChangedEvent
+//   admin_cxl  AdminProposedEvent
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminProposedEvent {
+    pub current: Address,
+    pub proposed: Address,
+    pub expires_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminChangedEvent {
+    pub previous: Address,
+    pub admin: Address,
+}
+
+/// Why a rotation step was refused.  Contracts map these onto their own
+/// error enums with `From`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TransferError {
+    /// No admin is stored yet.
+    NotInitialized,
+    /// No admin transfer is pending.
+    NoPendingAdmin,
+    /// The pending admin transfer was not accepted in time.
+    Expired,
+    /// The caller is not the nominated admin.
+    NotNominee,
+}
+
+/// Where a contract keeps its admin and how it publishes events.
+pub struct AdminTransfer<K> {
+    pub admin_key: K,
+    pub pending_key: K,
+    pub event_ns: Symbol,
+    pub event_version: u32,
+}
+
+impl<K: IntoVal<Env, Val>> AdminTransfer<K> {
+    /// Admin nominates a successor.  Replaces any earlier nomination.
+    pub fn propose(&self, env: &Env, new_admin: Address) -> Result<(), TransferError> {
+        let admin = self.admin(env)?;
+        admin.require_auth();
+
+        let pending = PendingAdmin {
+            admin: new_admin.clone(),
+            expires_at: env.ledger().timestamp() + ADMIN_TRANSFER_WINDOW,
+        };
+        env.storage().instance().set(&self.pending_key, &pending);
+
+        self.emit(
+            env,
+            symbol_short!("admin_new"),
+            AdminProposedEvent {
+                current: admin,
+                proposed: new_admin,
+                expires_at: pending.expires_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// The nominated admin takes over.  Must be signed by them.
+    pub fn accept(&self, env: &Env, new_admin: Address) -> Result<(), TransferError> {
+        new_admin.require_auth();
+
+        let admin = self.admin(env)?;
+        let pending = self.pending(env).ok_or(TransferError::NoPendingAdmin)?;
+        if pending.admin != new_admin {
+            return Err(TransferError::NotNominee);
+        }
+        if env.ledger().timestamp() > pending.expires_at {
+            return Err(TransferError::Expired);
+        }
+
+        env.storage().instance().set(&self.admin_key, &new_admin);
+        env.storage().instance().remove(&self.pending_key);
+
+        self.emit(
+            env,
+            symbol_short!("admin_set"),
+            AdminChangedEvent {
+                previous: admin,
+                admin: new_admin,
+            },
+        );
+        Ok(())
+    }
+
+    /// Admin withdraws a pending nomination.
+    pub fn cancel(&self, env: &Env) -> Result<(), TransferError> {
+        let admin = self.admin(env)?;
+        admin.require_auth();
+
+        let pending = self.pending(env).ok_or(TransferError::NoPendingAdmin)?;
+        env.storage().instance().remove(&self.pending_key);
+
+        self.emit(
+            env,
+            symbol_short!("admin_cxl"),
+            AdminProposedEvent {
+                current: admin,
+                proposed: pending.admin,
+                expires_at: pending.expires_at,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn pending(&self, env: &Env) -> Option<PendingAdmin> {
+        env.storage().instance().get(&self.pending_key)
+    }
+
+    fn admin(&self, env: &Env) -> Result<Address, TransferError> {
+        env.storage()
+            .instance()
+            .get(&self.admin_key)
+            .ok_or(TransferError::NotInitialized)
+    }
+
+    fn emit(&self, env: &Env, name: Symbol, data: impl IntoVal<Env, Val>) {
+        env.events()
+            .publish((self.event_ns.clone(), name, self.event_version), data);
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{
+        contract, contracterror, contractimpl,
+        testutils::{Address as _, Events, Ledger},
+        vec,
+    };
+
+    #[contracterror]
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+    #[repr(u32)]
+    pub enum TestError {
+        NotInitialized = 1,
+        NoPendingAdmin = 2,
+        AdminTransferExpired = 3,
+        Unauthorized = 4,
+    }
+
+    impl From<TransferError> for TestError {
+        fn from(e: TransferError) -> Self {
+            match e {
+                TransferError::NotInitialized => TestError::NotInitialized,
+                TransferError::NoPendingAdmin => TestError::NoPendingAdmin,
+                TransferError::Expired => TestError::AdminTransferExpired,
+                TransferError::NotNominee => TestError::Unauthorized,
+            }
+        }
+    }
+
+    #[contracttype]
+    pub enum DataKey {
+        Admin,
+        PendingAdmin,
+    }
+
+    const NS: Symbol = symbol_short!("test");
+
+    const ADMIN_TRANSFER: AdminTransfer<DataKey> = AdminTransfer {
+        admin_key: DataKey::Admin,
+        pending_key: DataKey::PendingAdmin,
+        event_ns: NS,
+        event_version: 1,
+    };
+
+    #[contract]
+    pub struct TestContract;
+
+    #[contractimpl]
+    impl TestContract {
+        pub fn init(env: Env, admin: Address) {
+            env.storage().instance().set(&DataKey::Admin, &admin);
+        }
+
+        pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), TestError> {
+            Ok(ADMIN_TRANSFER.propose(&env, new_admin)?)
+        }
+
+        pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), TestError> {
+            Ok(ADMIN_TRANSFER.accept(&env, new_admin)?)
+        }
+
+        pub fn cancel_admin_transfer(env: Env) -> Result<(), TestError> {
+            Ok(ADMIN_TRANSFER.cancel(&env)?)
+        }
+
+        pub fn get_admin(env: Env) -> Option<Address> {
+            env.storage().instance().get(&DataKey::Admin)
+        }
+
+        pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
+            ADMIN_TRANSFER.pending(&env)
+        }
+    }
+
+    fn setup() -> (Env, Address, TestContractClient<'static>) {
+        let env = Env::default();
+        env.mock_all_auths();
+        let client = TestContractClient::new(&env, &env.register(TestContract {}, ()));
+        let admin = Address::generate(&env);
+        client.init(&admin);
+        (env, admin, client)
+    }
+
+    #[test]
+    fn test_two_step_admin_rotation() {
+        let (env, admin, client) = setup();
+        let successor = Address::generate(&env);
+
+        client.propose_admin(&successor);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (NS, symbol_short!("admin_new"), 1u32).into_val(&env),
+                    AdminProposedEvent {
+                        current: admin.clone(),
+                        proposed: successor.clone(),
+                        expires_at: ADMIN_TRANSFER_WINDOW,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+        assert_eq!(
+            client.try_accept_admin(&Address::generate(&env)),
+            Err(Ok(TestError::Unauthorized))
+        );
+        assert_eq!(client.get_admin(), Some(admin.clone()));
+
+        client.accept_admin(&successor);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (NS, symbol_short!("admin_set"), 1u32).into_val(&env),
+                    AdminChangedEvent {
+                        previous: admin,
+                        admin: successor.clone(),
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+        assert_eq!(client.get_admin(), Some(successor));
+        assert_eq!(client.get_pending_admin(), None);
+    }
+
+    #[test]
+    fn test_admin_proposal_expires_or_is_cancelled() {
+        let (env, admin, client) = setup();
+        let successor = Address::generate(&env);
+
+        client.propose_admin(&successor);
+        env.ledger()
+            .with_mut(|l| l.timestamp += ADMIN_TRANSFER_WINDOW + 1);
+        assert_eq!(
+            client.try_accept_admin(&successor),
+            Err(Ok(TestError::AdminTransferExpired))
+        );
+
+        client.cancel_admin_transfer();
+        assert_eq!(
+            client.try_accept_admin(&successor),
+            Err(Ok(TestError::NoPendingAdmin))
+        );
+        assert_eq!(
+            client.try_cancel_admin_transfer(),
+            Err(Ok(TestError::NoPendingAdmin))
+        );
+        assert_eq!(client.get_admin(), Some(admin));
+    }
+
+    #[test]
+    fn test_propose_requires_admin_auth() {
+        let (env, _, client) = setup();
+        env.mock_auths(&[]);
+        assert!(client.try_propose_admin(&Address::generate(&env)).is_err());
+        assert_eq!(client.get_pending_admin(), None);
+    }
+}
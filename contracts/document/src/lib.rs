@@ -8,9 +8,15 @@
 //! altered since it was registered.
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, Address, Bytes, BytesN, Env, Vec,
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Bytes, BytesN, Env,
+    IntoVal, Symbol, Val, Vec,
 };
 
+pub use admin_transfer::{
+    AdminChangedEvent, AdminProposedEvent, PendingAdmin, ADMIN_TRANSFER_WINDOW,
+};
+use admin_transfer::{AdminTransfer, TransferError};
+
 // ── Errors ────────────────────────────────────────────────────────────────────
 
 #[contracterror]
@@ -23,6 +29,21 @@ pub enum DocumentError {
     Unauthorized = 4,
     AlreadyVerified = 5,
     HashMismatch = 6,
+    /// No admin transfer is pending.
+    NoPendingAdmin = 7,
+    /// The pending admin transfer was not accepted in time.
+    AdminTransferExpired = 8,
+}
+
+impl From<TransferError> for DocumentError {
+    fn from(e: TransferError) -> Self {
+        match e {
+            TransferError::NotInitialized => DocumentError::NotInitialized,
+            TransferError::NoPendingAdmin => DocumentError::NoPendingAdmin,
+            TransferError::Expired => DocumentError::AdminTransferExpired,
+            TransferError::NotNominee => DocumentError::Unauthorized,
+        }
+    }
 }
 
 // ── Types ─────────────────────────────────────────────────────────────────────
@@ -64,10 +85,79 @@ pub enum DataKey {
     Counter,
     Document(u64),
     ShipmentDocs(u64), // shipment_id → Vec<u64> of doc IDs
+    SchemaVersion,
+    PendingAdmin,
 }
 
 const TTL_LEDGERS: u32 = 6_307_200; // ~1 year
 
+/// Version of this contract's code; bumped with every release.
+pub const CONTRACT_VERSION: u32 = 1;
+/// Layout of `DocumentRecord`s and the id counter.  Only schema 1 has
+/// shipped, so a missing marker means 1 as well.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// ── Events ────────────────────────────────────────────────────────────────────
+//
+// Topics are `("document", <name>, EVENT_VERSION)`; the data is one of the
+// payload structs below.
+//
+//   uploaded   UploadedEvent
+//   verified   VerifiedEvent
+//   upgraded   UpgradedEvent
+//   migrated   MigratedEvent
+//   admin_new  AdminProposedEvent
+//   admin_set  AdminChangedEvent
+//   admin_cxl  AdminProposedEvent
+
+/// Bumped whenever a payload layout changes so indexers can branch on it.
+pub const EVENT_VERSION: u32 = 1;
+
+const EVENT_NS: Symbol = symbol_short!("document");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UploadedEvent {
+    pub doc_id: u64,
+    pub shipment_id: u64,
+    pub uploader: Address,
+    pub doc_type: DocumentType,
+    pub content_hash: BytesN<32>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerifiedEvent {
+    pub doc_id: u64,
+    pub shipment_id: u64,
+    pub verifier: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+    /// `CONTRACT_VERSION` of the code being replaced.
+    pub from_version: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigratedEvent {
+    pub schema_version: u32,
+}
+
+fn emit(env: &Env, name: Symbol, data: impl IntoVal<Env, Val>) {
+    env.events().publish((EVENT_NS, name, EVENT_VERSION), data);
+}
+
+const ADMIN_TRANSFER: AdminTransfer<DataKey> = AdminTransfer {
+    admin_key: DataKey::Admin,
+    pending_key: DataKey::PendingAdmin,
+    event_ns: EVENT_NS,
+    event_version: EVENT_VERSION,
+};
+
 // ── Contract ──────────────────────────────────────────────────────────────────
 
 #[contract]
@@ -83,6 +173,9 @@ impl DocumentContract {
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().persistent().set(&DataKey::Counter, &0u64);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
         Ok(())
     }
 
@@ -108,9 +201,9 @@ impl DocumentContract {
         let doc = DocumentRecord {
             id,
             shipment_id,
-            uploader,
-            doc_type,
-            content_hash,
+            uploader: uploader.clone(),
+            doc_type: doc_type.clone(),
+            content_hash: content_hash.clone(),
             ipfs_cid,
             uploaded_at: now,
             is_verified: false,
@@ -134,6 +227,17 @@ impl DocumentContract {
             .persistent()
             .set(&DataKey::ShipmentDocs(shipment_id), &list);
 
+        emit(
+            &env,
+            symbol_short!("uploaded"),
+            UploadedEvent {
+                doc_id: id,
+                shipment_id,
+                uploader,
+                doc_type,
+                content_hash,
+            },
+        );
         Ok(id)
     }
 
@@ -161,9 +265,19 @@ impl DocumentContract {
         }
 
         doc.is_verified = true;
-        doc.verified_by = Some(verifier);
+        doc.verified_by = Some(verifier.clone());
         doc.verified_at = env.ledger().timestamp();
         Self::store(&env, &doc);
+
+        emit(
+            &env,
+            symbol_short!("verified"),
+            VerifiedEvent {
+                doc_id,
+                shipment_id: doc.shipment_id,
+                verifier,
+            },
+        );
         Ok(())
     }
 
@@ -182,8 +296,98 @@ impl DocumentContract {
         Ok(doc.content_hash == hash_to_check)
     }
 
+    // ── Admin ─────────────────────────────────────────────────────────────
+
+    /// Admin nominates a successor, who must `accept_admin` within
+    /// `ADMIN_TRANSFER_WINDOW`.  Replaces any earlier nomination.
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), DocumentError> {
+        Ok(ADMIN_TRANSFER.propose(&env, new_admin)?)
+    }
+
+    /// The nominated admin takes over.  Must be signed by them.
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), DocumentError> {
+        Ok(ADMIN_TRANSFER.accept(&env, new_admin)?)
+    }
+
+    /// Admin withdraws a pending nomination.
+    pub fn cancel_admin_transfer(env: Env) -> Result<(), DocumentError> {
+        Ok(ADMIN_TRANSFER.cancel(&env)?)
+    }
+
+    // ── Upgrades ──────────────────────────────────────────────────────────
+
+    /// Admin swaps in new contract code; storage is kept.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), DocumentError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(DocumentError::NotInitialized)?;
+        admin.require_auth();
+
+        emit(
+            &env,
+            symbol_short!("upgraded"),
+            UpgradedEvent {
+                wasm_hash: new_wasm_hash.clone(),
+                from_version: CONTRACT_VERSION,
+            },
+        );
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(())
+    }
+
+    /// Admin brings storage up to this code's `SCHEMA_VERSION` after an
+    /// `upgrade`.  Nothing in the document layout has changed since launch,
+    /// so for now it only checks the marker; repeated calls do nothing.
+    pub fn migrate(env: Env) -> Result<(), DocumentError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(DocumentError::NotInitialized)?;
+        admin.require_auth();
+
+        if Self::schema_version(env.clone()) < SCHEMA_VERSION {
+            env.storage()
+                .instance()
+                .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+            emit(
+                &env,
+                symbol_short!("migrated"),
+                MigratedEvent {
+                    schema_version: SCHEMA_VERSION,
+                },
+            );
+        }
+        Ok(())
+    }
+
     // ── Queries ───────────────────────────────────────────────────────────
 
+    pub fn get_admin(env: Env) -> Result<Address, DocumentError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(DocumentError::NotInitialized)
+    }
+
+    pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
+        ADMIN_TRANSFER.pending(&env)
+    }
+
+    pub fn version(_env: Env) -> u32 {
+        CONTRACT_VERSION
+    }
+
+    /// Storage layout the stored records are known to be in.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(1)
+    }
+
     pub fn get_document(env: Env, doc_id: u64) -> Result<DocumentRecord, DocumentError> {
         Self::load(&env, doc_id)
     }
@@ -241,8 +445,8 @@ impl DocumentContract {
 mod tests {
     use super::*;
     use soroban_sdk::{
-        testutils::{Address as _, BytesN as _},
-        Bytes, BytesN, Env,
+        testutils::{Address as _, BytesN as _, Events},
+        vec, Bytes, BytesN, Env,
     };
 
     fn setup() -> (Env, Address, DocumentContractClient<'static>) {
@@ -411,4 +615,72 @@ mod tests {
         let result = client.try_get_document(&404u64);
         assert_eq!(result, Err(Ok(DocumentError::NotFound)));
     }
+
+    #[test]
+    fn test_register_and_verify_emit_events() {
+        let (env, admin, client) = setup();
+        let uploader = Address::generate(&env);
+
+        let (id, hash) = register(&env, &client, &uploader, 3);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (EVENT_NS, symbol_short!("uploaded"), EVENT_VERSION).into_val(&env),
+                    UploadedEvent {
+                        doc_id: id,
+                        shipment_id: 3,
+                        uploader: uploader.clone(),
+                        doc_type: DocumentType::BillOfLading,
+                        content_hash: hash,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.verify_document(&admin, &id);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (EVENT_NS, symbol_short!("verified"), EVENT_VERSION).into_val(&env),
+                    VerifiedEvent {
+                        doc_id: id,
+                        shipment_id: 3,
+                        verifier: admin.clone(),
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upgrade_requires_admin() {
+        let (env, _, client) = setup();
+        assert_eq!(client.version(), CONTRACT_VERSION);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+
+        env.mock_auths(&[]);
+        assert!(client
+            .try_upgrade(&BytesN::from_array(&env, &[0; 32]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_migrate_is_a_no_op_on_the_current_schema() {
+        let (env, _, client) = setup();
+        client.migrate();
+        client.migrate();
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+        assert_eq!(env.events().all().len(), 0);
+
+        env.mock_auths(&[]);
+        assert!(client.try_migrate().is_err());
+    }
 }
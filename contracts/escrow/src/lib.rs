@@ -1,6 +1,14 @@
 #![no_std]
 
-use soroban_sdk::{contract, contracterror, contractimpl, contracttype, token, Address, Env};
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    Address, BytesN, Env, IntoVal, Map, String, Symbol, Val, Vec,
+};
+
+pub use admin_transfer::{
+    AdminChangedEvent, AdminProposedEvent, PendingAdmin, ADMIN_TRANSFER_WINDOW,
+};
+use admin_transfer::{AdminTransfer, TransferError};
 
 // ── Errors ────────────────────────────────────────────────────────────────────
 
@@ -17,6 +25,95 @@ pub enum EscrowError {
     Unauthorized = 7,
     InvalidAmount = 8,
     InsufficientBalance = 9,
+    /// The single-admin entry point is disabled: a signer set is configured,
+    /// or the ruling takes an arbitration fee.
+    MultisigRequired = 10,
+    NotSigner = 11,
+    AlreadyApproved = 12,
+    /// No such proposal, or it expired before it was executed.
+    ProposalNotFound = 13,
+    ProposalExecuted = 14,
+    ThresholdNotMet = 15,
+    InvalidThreshold = 16,
+    /// The scheduled settlement's challenge window has not elapsed yet.
+    TimelockActive = 17,
+    /// The entry point is frozen by the circuit breaker.
+    Paused = 18,
+    /// Milestone schedule is empty, has a zero share or does not sum to 100%.
+    InvalidMilestones = 19,
+    MilestoneNotFound = 20,
+    MilestoneAlreadyReleased = 21,
+    /// Shares above 100%, or an arbitration fee above
+    /// `MAX_ARBITRATION_FEE_BPS` or not paid to the arbitration treasury.
+    InvalidRuling = 22,
+    /// The token is not on the settlement allowlist.
+    TokenNotAllowed = 23,
+    /// Commission rate above `MAX_COMMISSION_BPS`.
+    InvalidCommission = 24,
+    /// Cancellation fee above 100%.
+    InvalidFeeTable = 25,
+    /// No shipment contract is linked for trustless settlement.
+    ShipmentContractNotSet = 26,
+    /// The linked shipment is missing or not yet Completed / Cancelled.
+    ShipmentNotSettled = 27,
+    /// The shipment's shipper or carrier differs from the escrow's.
+    PartyMismatch = 28,
+    /// No confirmation window is configured, so carriers cannot self-claim.
+    AutoReleaseDisabled = 29,
+    /// The shipper's confirmation window has not elapsed yet.
+    DeadlineNotReached = 30,
+    /// The escrow was funded without acceptance or pickup deadlines.
+    NoDeadlineSet = 31,
+    /// No admin transfer is pending.
+    NoPendingAdmin = 32,
+    /// The pending admin transfer was not accepted in time.
+    AdminTransferExpired = 33,
+    /// Nothing beyond the committed amount is on deposit.
+    NoExcess = 34,
+    /// The escrow has settled; the admin must `reopen_escrow` before the
+    /// shipment is funded again.
+    SettlementFinal = 35,
+    /// A batch is empty or longer than `MAX_BATCH_SIZE`.
+    InvalidBatch = 36,
+    /// Records from an older schema are not all migrated yet.
+    MigrationPending = 37,
+    /// The carrier's free bond balance does not cover the amount.
+    InsufficientBond = 38,
+    /// The bond withdrawal cooldown has not elapsed.
+    BondLocked = 39,
+    /// The ruling slashes a bond the carrier never posted.
+    NoBondPosted = 40,
+    /// No bond withdrawal has been requested.
+    NoWithdrawalPending = 41,
+    /// The escrow's payout has not been assigned.
+    NotAssigned = 42,
+    /// The escrow was not funded as a stream.
+    NotStreaming = 43,
+    /// Vesting starts once the linked shipment is in transit.
+    StreamNotStarted = 44,
+    /// Nothing has vested beyond what was already withdrawn.
+    NothingVested = 45,
+    /// The escrow has no leg at that index.
+    LegNotFound = 46,
+    LegAlreadyPaid = 47,
+    /// The shipper has not confirmed the leg on the linked shipment.
+    LegNotConfirmed = 48,
+    /// Multi-leg escrows pay out per leg through `release_leg`.
+    PaidPerLeg = 49,
+    /// A leg has no carrier yet; multi-leg escrows are funded once every leg
+    /// is accepted.
+    LegUnassigned = 50,
+}
+
+impl From<TransferError> for EscrowError {
+    fn from(e: TransferError) -> Self {
+        match e {
+            TransferError::NotInitialized => EscrowError::NotInitialized,
+            TransferError::NoPendingAdmin => EscrowError::NoPendingAdmin,
+            TransferError::Expired => EscrowError::AdminTransferExpired,
+            TransferError::NotNominee => EscrowError::Unauthorized,
+        }
+    }
 }
 
 // ── Types ─────────────────────────────────────────────────────────────────────
@@ -24,242 +121,980 @@ pub enum EscrowError {
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum EscrowStatus {
-    /// Escrow record created, waiting for shipper to deposit funds.
+    /// Escrow record created; deposits do not yet cover `amount`.
     Pending,
     /// Funds are held in the contract.
     Funded,
     /// Payment released to carrier — shipment completed.
     Released,
-    /// Funds returned to shipper — shipment cancelled.
+    /// Funds returned to payer — shipment cancelled.
     Refunded,
     /// In dispute — awaiting admin resolution.
     Disputed,
+    /// Release or refund scheduled — executable once the challenge window ends.
+    Scheduled(SettlementKind),
+    /// Dispute ruled with the funds split between carrier and shipper.
+    Split,
+}
+
+impl EscrowStatus {
+    fn kind(&self) -> StatusKind {
+        match self {
+            EscrowStatus::Pending => StatusKind::Pending,
+            EscrowStatus::Funded => StatusKind::Funded,
+            EscrowStatus::Released => StatusKind::Released,
+            EscrowStatus::Refunded => StatusKind::Refunded,
+            EscrowStatus::Disputed => StatusKind::Disputed,
+            EscrowStatus::Scheduled(_) => StatusKind::Scheduled,
+            EscrowStatus::Split => StatusKind::Split,
+        }
+    }
+}
+
+/// `EscrowStatus` without its payload, for status queries.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StatusKind {
+    Pending,
+    Funded,
+    Released,
+    Refunded,
+    Disputed,
+    Scheduled,
+    Split,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SettlementKind {
+    Release,
+    Refund,
+    /// Pay out the tranche at this index of the milestone schedule.
+    Milestone(u32),
+    /// Refund the shipper minus the cancellation fee for this stage, which
+    /// goes to the carrier as compensation.
+    Cancel(CancelStage),
+}
+
+/// How far the shipment had progressed when it was cancelled.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CancelStage {
+    Pending,
+    Accepted,
+    InTransit,
+}
+
+/// Carrier compensation owed on cancellation, in basis points of the held
+/// funds, per stage.  All zero by default.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CancellationFees {
+    pub pending: u32,
+    pub accepted: u32,
+    pub in_transit: u32,
+}
+
+impl CancellationFees {
+    fn bps(&self, stage: CancelStage) -> u32 {
+        match stage {
+            CancelStage::Pending => self.pending,
+            CancelStage::Accepted => self.accepted,
+            CancelStage::InTransit => self.in_transit,
+        }
+    }
+}
+
+/// One tranche of a milestone payout schedule.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Milestone {
+    /// e.g. `pickup`, `border`, `delivery`.
+    pub label: Symbol,
+    /// Share of the escrowed amount, in basis points (10 000 = 100%).
+    pub bps: u32,
+    pub released: bool,
+}
+
+/// Linear vesting of a streaming escrow over the expected transit time.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stream {
+    /// Expected transit time in seconds; `0` for a lump-sum escrow.
+    pub duration: u64,
+    /// When the shipment went in transit, once pinned by `start_stream`, a
+    /// draw or a dispute.  Until then vesting runs from the linked shipment's
+    /// `picked_up_at`.
+    pub started_at: Option<u64>,
+    /// When a dispute froze vesting.
+    pub frozen_at: Option<u64>,
+}
+
+/// One leg of a multi-leg escrow, paid to that leg's carrier once the
+/// shipper confirms it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegPayout {
+    /// The leg's price on the shipment, before commission.
+    pub price: i128,
+    /// The leg's carrier when the escrow was funded; the leg only pays out
+    /// to them.
+    pub carrier: Address,
+    /// Who the leg was paid to; `None` while outstanding.
+    pub paid_to: Option<Address>,
+}
+
+/// Admin ruling on a disputed escrow.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputeRuling {
+    /// Carrier's share of what is left after the arbitration fee, in basis
+    /// points.  The payer receives the rest.
+    pub carrier_bps: u32,
+    /// Arbitration fee taken off the top, in basis points of the held funds;
+    /// at most `MAX_ARBITRATION_FEE_BPS`.
+    pub fee_bps: u32,
+    /// Required when `fee_bps > 0`, and must be the arbitration treasury.
+    pub fee_recipient: Option<Address>,
+    /// Share of the carrier's posted bond paid to the shipper, in basis
+    /// points.  Only allowed when the ruling goes at least partly against
+    /// the carrier.
+    pub slash_bps: u32,
+}
+
+/// Token amounts a ruling actually paid out.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitAmounts {
+    pub carrier: i128,
+    /// Returned to the escrow's payer.
+    pub shipper: i128,
+    pub fee: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Resolution {
+    Unresolved,
+    Ruled(DisputeRuling, SplitAmounts),
 }
 
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct EscrowRecord {
     pub shipment_id: u64,
+    /// Funding round for this shipment, from 1.  Earlier rounds are archived
+    /// and readable through `get_escrow_round`.
+    pub round: u32,
+    /// Confirms delivery and raises disputes.
     pub shipper: Address,
+    /// Funded the escrow and receives every refund.  The shipper unless a
+    /// consignee or forwarder paid via `fund_escrow_for`.
+    pub payer: Address,
     pub carrier: Address,
+    /// SEP-41 token the escrow was funded in; every payout uses it.
+    pub token: Address,
     /// Amount of tokens held (in the token's base unit, e.g. stroops for XLM).
+    /// For an incrementally funded escrow, the agreed price.
     pub amount: i128,
+    /// Total deposited so far.  Anything beyond `amount` is withdrawable.
+    pub deposited: i128,
     pub status: EscrowStatus,
     pub funded_at: u64,
     pub settled_at: u64,
+    /// While `Scheduled`: ledger timestamp from which `execute_settlement`
+    /// may be called.  `0` otherwise.
+    pub execute_after: u64,
+    /// Commission terms in force when the settlement was scheduled, charged
+    /// at execution so a rate change inside the challenge window does not
+    /// reach it.  `Uncharged` when nothing is scheduled.
+    pub commission: CommissionTerms,
+    /// Empty for lump-sum escrows.
+    pub milestones: Vec<Milestone>,
+    /// Paid out to the carrier so far through milestone tranches.
+    pub released_amount: i128,
+    /// Outcome of the dispute ruling, if there was one.
+    pub resolution: Resolution,
+    /// Platform commission deducted from payouts so far.
+    pub fee_charged: i128,
+    /// Timestamp by which a carrier must accept, or the shipper may reclaim.
+    /// `0` when no deadline applies.
+    pub accept_by: u64,
+    /// Timestamp by which the cargo must be in transit.  `0` when none.
+    pub pickup_by: u64,
+    /// Late-delivery penalty refunded to the payer at release.
+    pub penalty: i128,
+    /// Carrier bond committed to this escrow; freed on settlement.
+    pub bond: i128,
+    /// Part of `bond` a dispute ruling paid to the shipper.
+    pub bond_slashed: i128,
+    /// Payout assignment chain, oldest first.  The last assignee receives
+    /// every carrier payout; empty means the carrier does.
+    pub assignees: Vec<Address>,
+    pub stream: Stream,
+    /// Per-leg payouts of a multi-leg escrow; empty otherwise.  `carrier` is
+    /// the first leg's carrier.
+    pub legs: Vec<LegPayout>,
 }
 
+/// `EscrowRecord` as stored under schema 1, before per-escrow tokens.
 #[contracttype]
-pub enum DataKey {
-    Admin,
-    TokenContract,
-    Escrow(u64), // shipment_id → EscrowRecord
+#[derive(Clone, Debug)]
+pub struct EscrowRecordV1 {
+    pub shipment_id: u64,
+    pub shipper: Address,
+    pub carrier: Address,
+    pub amount: i128,
+    pub status: EscrowStatus,
+    pub funded_at: u64,
+    pub settled_at: u64,
 }
 
-const TTL_LEDGERS: u32 = 6_307_200; // ~1 year
+/// Deadlines stamped onto each escrow at funding, in seconds from funding.
+/// `0` disables a deadline.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeadlinePolicy {
+    pub accept_within: u64,
+    pub pickup_within: u64,
+}
 
-// ── Contract ──────────────────────────────────────────────────────────────────
+/// Platform commission taken from escrow payouts and sent to `treasury`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommissionConfig {
+    /// Default rate in basis points; per-shipper overrides take precedence.
+    pub bps: u32,
+    pub treasury: Address,
+    /// Whether refunds to the shipper are charged too.
+    pub charge_on_refund: bool,
+}
 
-#[contract]
-pub struct EscrowContract;
+/// Commission snapshot taken when a settlement is scheduled.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CommissionTerms {
+    Uncharged,
+    /// The config with any shipper override folded into `bps`.
+    Charged(CommissionConfig),
+}
 
-#[contractimpl]
-impl EscrowContract {
-    // ── Setup ─────────────────────────────────────────────────────────────
+/// Which group of entry points `pause` / `unpause` acts on.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PauseScope {
+    /// `fund_escrow`.
+    Funding,
+    /// Release, refund, dispute resolution and the multisig flow.
+    Settlement,
+    /// Both of the above plus `raise_dispute`.
+    All,
+}
 
-    /// One-time initialisation.  `token_contract` is the SEP-41 token address
-    /// (e.g. the Stellar native-XLM wrapper contract on Soroban).
-    pub fn initialize(
-        env: Env,
-        admin: Address,
-        token_contract: Address,
-    ) -> Result<(), EscrowError> {
-        if env.storage().instance().has(&DataKey::Admin) {
-            return Err(EscrowError::AlreadyInitialized);
-        }
-        env.storage().instance().set(&DataKey::Admin, &admin);
-        env.storage()
-            .instance()
-            .set(&DataKey::TokenContract, &token_contract);
-        Ok(())
-    }
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PauseState {
+    pub funding: bool,
+    pub settlement: bool,
+}
 
-    // ── Shipper actions ───────────────────────────────────────────────────
+/// Action a multisig proposal executes once approved.  Contract-wide actions
+/// ignore the proposal's `shipment_id`; pass `0`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EscrowAction {
+    Release,
+    Refund,
+    /// `true` → funds go to carrier, `false` → funds returned to payer.
+    ResolveDispute(bool),
+    ReleaseMilestone(u32),
+    ResolveSplit(DisputeRuling),
+    Cancel,
+    Reopen,
+    /// Contract-wide: replace the signer set and threshold.
+    SetSigners(Vec<Address>, u32),
+    /// Contract-wide: `sweep_surplus(token, to, amount)`.
+    SweepSurplus(Address, Address, i128),
+    /// Contract-wide: `set_shipment_contract(contract)`.
+    SetShipmentContract(Address),
+    /// Contract-wide: `set_arbitration_treasury(treasury)`.
+    SetArbitrationTreasury(Address),
+    /// Contract-wide: `set_commission(bps, treasury, charge_on_refund)`.
+    SetCommission(u32, Address, bool),
+    /// Contract-wide: `set_shipper_commission(shipper, bps)`.
+    SetShipperCommission(Address, Option<u32>),
+    /// Contract-wide: `upgrade(new_wasm_hash)`.
+    Upgrade(BytesN<32>),
+    /// Contract-wide: `migrate(shipment_ids, complete)`.
+    Migrate(Vec<u64>, bool),
+}
 
-    /// Shipper locks funds for a shipment.
-    ///
-    /// **Pre-condition:** the shipper must have called `approve` on the token
-    /// contract, granting this escrow contract an allowance ≥ `amount`.
-    ///
-    /// The contract pulls the tokens from `shipper` via `transfer_from`.
-    pub fn fund_escrow(
-        env: Env,
-        shipper: Address,
-        carrier: Address,
-        shipment_id: u64,
-        amount: i128,
-    ) -> Result<(), EscrowError> {
-        shipper.require_auth();
+impl EscrowAction {
+    fn is_contract_wide(&self) -> bool {
+        matches!(
+            self,
+            EscrowAction::SetSigners(..)
+                | EscrowAction::SweepSurplus(..)
+                | EscrowAction::SetShipmentContract(..)
+                | EscrowAction::SetArbitrationTreasury(..)
+                | EscrowAction::SetCommission(..)
+                | EscrowAction::SetShipperCommission(..)
+                | EscrowAction::Upgrade(..)
+                | EscrowAction::Migrate(..)
+        )
+    }
+}
 
-        if amount <= 0 {
-            return Err(EscrowError::InvalidAmount);
-        }
+/// What a batch settlement did with one shipment.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BatchOutcome {
+    Settled,
+    /// A settlement delay is configured; see `execute_settlement`.
+    Scheduled,
+    /// Skipped with this `EscrowError` code; nothing changed for the shipment.
+    Failed(u32),
+}
 
-        // Ensure no double-funding for the same shipment.
-        if env
-            .storage()
-            .persistent()
-            .has(&DataKey::Escrow(shipment_id))
-        {
-            let existing: EscrowRecord = env
-                .storage()
-                .persistent()
-                .get(&DataKey::Escrow(shipment_id))
-                .unwrap();
-            if existing.status == EscrowStatus::Funded {
-                return Err(EscrowError::AlreadyFunded);
-            }
-        }
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchResult {
+    pub shipment_id: u64,
+    pub outcome: BatchOutcome,
+}
 
-        // Pull tokens from shipper into this contract.
-        let token_addr: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::TokenContract)
-            .ok_or(EscrowError::NotInitialized)?;
+/// An N-of-M approval round for a settlement action on one shipment.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Proposal {
+    pub id: u64,
+    pub shipment_id: u64,
+    pub action: EscrowAction,
+    pub proposer: Address,
+    /// Escrow round the action targets; `0` for contract-wide actions.
+    pub round: u32,
+    pub approvals: Vec<Address>,
+    pub executed: bool,
+    pub created_at: u64,
+    /// Approvals and execution are refused after this.
+    pub expires_at: u64,
+    pub executed_at: u64,
+}
 
-        let token = token::Client::new(&env, &token_addr);
-        // transfer_from: spender=this_contract, from=shipper, to=this_contract, amount
-        token.transfer_from(
-            &env.current_contract_address(),
-            &shipper,
-            &env.current_contract_address(),
-            &amount,
-        );
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PartyRole {
+    Shipper,
+    Carrier,
+}
 
-        let now = env.ledger().timestamp();
-        let record = EscrowRecord {
-            shipment_id,
-            shipper,
-            carrier,
-            amount,
-            status: EscrowStatus::Funded,
-            funded_at: now,
-            settled_at: 0,
-        };
+/// An append-only list of `(shipment_id, round)` entries.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IndexKey {
+    /// Every round the address took part in, in that role.
+    Party(PartyRole, Address),
+    /// Every time an escrow entered the status.  Entries go stale when it
+    /// moves on; queries skip them.
+    Status(StatusKind),
+}
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Escrow(shipment_id), &record);
-        env.storage().persistent().extend_ttl(
-            &DataKey::Escrow(shipment_id),
-            TTL_LEDGERS,
-            TTL_LEDGERS,
-        );
+/// What the indexes last recorded for one escrow.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IndexState {
+    pub round: u32,
+    pub status: StatusKind,
+    /// Position of the live entry in the `IndexKey::Status(status)` list.
+    pub slot: u32,
+    /// Counted towards both parties' `get_locked`.
+    pub locked: i128,
+    /// Tokens the contract holds for this escrow, counted in `Liabilities`.
+    pub held: i128,
+}
 
-        Ok(())
-    }
+/// A carrier's standing bond in one token.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BondAccount {
+    pub balance: i128,
+    /// Posted to open escrows; freed when they settle.
+    pub committed: i128,
+    /// Requested for withdrawal; claimable from `unlock_at`.
+    pub withdrawing: i128,
+    pub unlock_at: u64,
+}
 
-    // ── Settlement ────────────────────────────────────────────────────────
+/// A token's balance held by the contract against what it owes escrows.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reconciliation {
+    pub balance: i128,
+    pub liabilities: i128,
+    /// `balance - liabilities`; negative means a shortfall.
+    pub surplus: i128,
+}
 
-    /// Release locked funds to the carrier.
-    /// Called when a shipment is Completed (shipper confirmed delivery).
-    /// In production this would be called by an authorized shipment contract;
-    /// for now admin can also trigger it after off-chain verification.
-    pub fn release_payment(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Admin)
-            .ok_or(EscrowError::NotInitialized)?;
-        admin.require_auth();
+/// One page of an escrow query, newest first.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct EscrowPage {
+    pub records: Vec<EscrowRecord>,
+    /// Pass back to fetch the next page; `None` on the last one.  A page may
+    /// hold fewer than `limit` records, or none, before the end.
+    pub next_cursor: Option<u32>,
+}
 
-        let mut record = Self::load(&env, shipment_id)?;
+/// A shorter settlement delay waiting out the one in force.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingDelay {
+    pub delay_seconds: u64,
+    pub effective_at: u64,
+}
 
-        if record.status != EscrowStatus::Funded {
-            return Err(EscrowError::InvalidStatus);
-        }
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    /// Schema 1's single settlement token; folded into `AllowedTokens` by
+    /// `migrate`.
+    TokenContract,
+    AllowedTokens,
+    Escrow(u64),           // shipment_id → current EscrowRecord
+    EscrowRound(u64, u32), // (shipment_id, round) → archived EscrowRecord
+    Reopened(u64),         // shipment_id → admin allowed another round
+    Deposits(u64),         // shipment_id → depositor → amount, once `deposit` is used
+    Indexed(u64),          // shipment_id → IndexState
+    IndexLen(IndexKey),
+    IndexEntry(IndexKey, u32), // position → (shipment_id, round)
+    StatusCount(StatusKind),
+    Locked(PartyRole, Address, Address), // (role, party, token) → amount
+    Liabilities(Address),                // token → held for escrows and bonds
+    Bond(Address, Address),              // (carrier, token) → BondAccount
+    BondCooldown,
+    Signers,
+    Threshold,
+    ProposalCounter,
+    Proposal(u64), // proposal_id → Proposal
+    SettlementDelay,
+    Pauser,
+    PauseState,
+    Commission,
+    ShipperCommission(Address), // shipper → bps override
+    AccruedFees(Address),       // token → commission collected
+    ArbitrationTreasury,
+    CancellationFees,
+    ShipmentContract,
+    ConfirmationWindow,
+    DeadlinePolicy,
+    SchemaVersion,
+    PendingAdmin,
+    PendingDelay,
+}
 
-        let token_addr: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::TokenContract)
-            .unwrap();
-        let token = token::Client::new(&env, &token_addr);
-        token.transfer(
-            &env.current_contract_address(),
-            &record.carrier,
-            &record.amount,
-        );
+const TTL_LEDGERS: u32 = 6_307_200; // ~1 year
 
-        record.status = EscrowStatus::Released;
-        record.settled_at = env.ledger().timestamp();
-        Self::store(&env, &record);
-        Ok(())
-    }
+/// Version of this contract's code; bumped with every release.
+pub const CONTRACT_VERSION: u32 = 2;
+/// Layout of persisted records.  1 = launch layout (single token, lump sum); 2 is the
+/// current `EscrowRecord`.
+/// The marker arrived with schema 2, so a contract without one is on 1.
+pub const SCHEMA_VERSION: u32 = 2;
 
-    /// Refund locked funds back to the shipper.
-    /// Called when a shipment is Cancelled.
-    pub fn refund_payment(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Admin)
-            .ok_or(EscrowError::NotInitialized)?;
-        admin.require_auth();
+const BPS_DENOMINATOR: u32 = 10_000;
+const MAX_MILESTONES: u32 = 10;
 
-        let mut record = Self::load(&env, shipment_id)?;
+/// Largest platform commission rate, default or per shipper, in basis points.
+pub const MAX_COMMISSION_BPS: u32 = 1_000;
 
-        if record.status != EscrowStatus::Funded {
-            return Err(EscrowError::InvalidStatus);
-        }
+/// Largest arbitration fee a dispute ruling may take, in basis points.
+pub const MAX_ARBITRATION_FEE_BPS: u32 = 1_000;
 
-        let token_addr: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::TokenContract)
-            .unwrap();
-        let token = token::Client::new(&env, &token_addr);
-        token.transfer(
-            &env.current_contract_address(),
-            &record.shipper,
-            &record.amount,
-        );
+/// How long a multisig proposal stays open for approval and execution.
+pub const PROPOSAL_LIFETIME: u64 = 7 * 24 * 60 * 60;
 
-        record.status = EscrowStatus::Refunded;
-        record.settled_at = env.ledger().timestamp();
-        Self::store(&env, &record);
-        Ok(())
-    }
+/// Most shipments `release_batch` / `refund_batch` settle in one call; keeps
+/// a full batch well inside the per-transaction resource limits.
+pub const MAX_BATCH_SIZE: u32 = 25;
 
-    /// Raise a dispute for the escrow (mirrors the shipment dispute).
-    /// Either party can call this; admin then resolves via release or refund.
-    pub fn raise_dispute(env: Env, caller: Address, shipment_id: u64) -> Result<(), EscrowError> {
-        caller.require_auth();
+/// Default wait between requesting and withdrawing a carrier bond.
+pub const DEFAULT_BOND_COOLDOWN: u64 = 3 * 24 * 60 * 60;
 
-        let mut record = Self::load(&env, shipment_id)?;
+/// Largest page the escrow queries return; also the default.
+pub const MAX_PAGE_SIZE: u32 = 10;
 
-        let is_party = record.shipper == caller || record.carrier == caller;
-        if !is_party {
-            return Err(EscrowError::Unauthorized);
-        }
-        if record.status != EscrowStatus::Funded {
-            return Err(EscrowError::InvalidStatus);
-        }
+// ── Events ────────────────────────────────────────────────────────────────────
+//
+// Topics are `("escrow", <name>, EVENT_VERSION)`; the data is one of the
+// payload structs below.
+//
+//   created    FundedEvent — incremental escrow opened, `amount` expected
+//   deposit    DepositEvent
+//   funded     FundedEvent
+//   reopened   ReopenedEvent
+//   adjusted   AdjustedEvent
+//   withdrawn  SettledEvent — one per depositor
+//   abandoned  SettledEvent — one per depositor of a cancelled `Pending` escrow
+//   allowlist  AllowlistEvent
+//   commissn   CommissionEvent
+//   override   OverrideEvent
+//   treasury   TreasuryEvent — arbitration fees go here
+//   released   SettledEvent
+//   milestone  MilestoneEvent
+//   leg_paid   LegPaidEvent
+//   stream_on  StreamStartEvent
+//   streamed   StreamEvent
+//   refunded   SettledEvent
+//   penalty    PenaltyEvent
+//   cancelled  CancelledEvent
+//   disputed   DisputedEvent
+//   resolved   ResolvedEvent
+//   assigned   AssignedEvent
+//   revoked    AssignedEvent — `from` gave the payout back to `to`
+//   bond_in    BondEvent
+//   bond_post  BondPostEvent
+//   bond_free  BondPostEvent
+//   slashed    BondPostEvent
+//   bond_req   BondWithdrawalEvent
+//   bond_out   BondEvent
+//   bondcool   DelayEvent
+//   scheduled  ScheduledEvent
+//   proposed   ProposalEvent
+//   approved   ProposalEvent
+//   executed   ExecutedEvent
+//   signers    SignersEvent
+//   delay      DelayEvent
+//   delay_low  PendingDelay — takes effect at `effective_at`
+//   cancelfee  CancellationFees
+//   shipments  ShipmentContractEvent
+//   window     WindowEvent
+//   deadlines  DeadlinePolicy
+//   swept      SweepEvent
+//   pauser     PauserEvent
+//   paused     PauseEvent
+//   unpaused   PauseEvent
+//   upgraded   UpgradedEvent
+//   migrated   MigratedEvent
+//   admin_new  AdminProposedEvent
+//   admin_set  AdminChangedEvent
+//   admin_cxl  AdminProposedEvent
 
-        record.status = EscrowStatus::Disputed;
-        Self::store(&env, &record);
-        Ok(())
-    }
+/// Bumped whenever a payload layout changes so indexers can branch on it.
+pub const EVENT_VERSION: u32 = 1;
 
-    /// Admin resolves a disputed escrow.
-    /// `release_to_carrier = true` → funds go to carrier.
-    /// `release_to_carrier = false` → funds returned to shipper.
-    pub fn resolve_dispute(
+const EVENT_NS: Symbol = symbol_short!("escrow");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FundedEvent {
+    pub shipment_id: u64,
+    pub round: u32,
+    pub shipper: Address,
+    pub payer: Address,
+    pub carrier: Address,
+    pub token: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReopenedEvent {
+    pub shipment_id: u64,
+    /// The settled round; the next funding opens `round + 1`.
+    pub round: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositEvent {
+    pub shipment_id: u64,
+    pub payer: Address,
+    pub amount: i128,
+    /// Running total after this deposit.
+    pub deposited: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdjustedEvent {
+    pub shipment_id: u64,
+    pub amount: i128,
+    pub deposited: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowlistEvent {
+    pub token: Address,
+    pub allowed: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SettledEvent {
+    pub shipment_id: u64,
+    pub recipient: Address,
+    /// Net of `fee`.
+    pub amount: i128,
+    pub fee: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamStartEvent {
+    pub shipment_id: u64,
+    pub started_at: u64,
+    pub duration: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamEvent {
+    pub shipment_id: u64,
+    pub recipient: Address,
+    /// Net of commission.
+    pub amount: i128,
+    pub fee: i128,
+    /// Total vested so far.
+    pub vested: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MilestoneEvent {
+    pub shipment_id: u64,
+    pub index: u32,
+    pub label: Symbol,
+    /// Net of `fee`.
+    pub amount: i128,
+    pub fee: i128,
+    /// Still held after this tranche; `0` once the escrow is fully released.
+    pub remaining: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegPaidEvent {
+    pub shipment_id: u64,
+    pub index: u32,
+    pub recipient: Address,
+    /// Net of `fee`.
+    pub amount: i128,
+    pub fee: i128,
+    /// Still held after this leg; `0` once every leg is paid.
+    pub remaining: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PenaltyEvent {
+    pub shipment_id: u64,
+    pub hours_late: u64,
+    /// Withheld from the carrier and refunded to the payer.
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CancelledEvent {
+    pub shipment_id: u64,
+    pub stage: CancelStage,
+    /// Net amounts paid after commission.
+    pub refund: i128,
+    pub compensation: i128,
+    pub fee: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputedEvent {
+    pub shipment_id: u64,
+    pub raised_by: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssignedEvent {
+    pub shipment_id: u64,
+    pub from: Address,
+    pub to: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondEvent {
+    pub carrier: Address,
+    pub token: Address,
+    pub amount: i128,
+    /// Standing balance afterwards.
+    pub balance: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondPostEvent {
+    pub shipment_id: u64,
+    pub carrier: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondWithdrawalEvent {
+    pub carrier: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub unlock_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolvedEvent {
+    pub shipment_id: u64,
+    pub ruling: DisputeRuling,
+    pub amounts: SplitAmounts,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScheduledEvent {
+    pub shipment_id: u64,
+    pub kind: SettlementKind,
+    pub execute_after: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProposalEvent {
+    pub proposal_id: u64,
+    pub shipment_id: u64,
+    pub signer: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExecutedEvent {
+    pub proposal_id: u64,
+    pub shipment_id: u64,
+    pub action: EscrowAction,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignersEvent {
+    pub signers: Vec<Address>,
+    pub threshold: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DelayEvent {
+    pub delay_seconds: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommissionEvent {
+    pub config: CommissionConfig,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverrideEvent {
+    pub shipper: Address,
+    /// `None` when the override was cleared.
+    pub bps: Option<u32>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TreasuryEvent {
+    pub treasury: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShipmentContractEvent {
+    pub contract: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WindowEvent {
+    pub window_seconds: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SweepEvent {
+    pub token: Address,
+    pub to: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PauserEvent {
+    pub pauser: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PauseEvent {
+    pub scope: PauseScope,
+    pub by: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+    /// `CONTRACT_VERSION` of the code being replaced.
+    pub from_version: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigratedEvent {
+    pub schema_version: u32,
+}
+
+fn emit(env: &Env, name: Symbol, data: impl IntoVal<Env, Val>) {
+    env.events().publish((EVENT_NS, name, EVENT_VERSION), data);
+}
+
+const ADMIN_TRANSFER: AdminTransfer<DataKey> = AdminTransfer {
+    admin_key: DataKey::Admin,
+    pending_key: DataKey::PendingAdmin,
+    event_ns: EVENT_NS,
+    event_version: EVENT_VERSION,
+};
+
+// ── Shipment contract interface ───────────────────────────────────────────────
+//
+// Mirrors of the shipment contract's types, decoded from `get_shipment` for
+// trustless settlement.  Field names and variants must stay in sync with
+// `contracts/shipment/src/lib.rs`.
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ShipmentStatus {
+    Created,
+    Accepted,
+    InTransit,
+    Delivered,
+    Completed,
+    Disputed,
+    Cancelled,
+    PartiallyCompleted,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shipment {
+    pub id: u64,
+    pub shipper: Address,
+    pub carrier: Option<Address>,
+    pub origin: String,
+    pub destination: String,
+    pub cargo_description: String,
+    pub weight_kg: u32,
+    pub price: i128,
+    pub status: ShipmentStatus,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub deliver_by: u64,
+    pub penalty: LatePenalty,
+    pub picked_up_at: u64,
+    pub delivered_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LatePenalty {
+    pub bps_per_hour: u32,
+    pub max_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Leg {
+    pub carrier: Option<Address>,
+    pub price: i128,
+    pub status: ShipmentStatus,
+    pub delivered_at: u64,
+}
+
+#[allow(dead_code)]
+#[contractclient(name = "ShipmentContractClient")]
+trait ShipmentContractInterface {
+    fn get_shipment(env: Env, shipment_id: u64) -> Shipment;
+    fn get_legs(env: Env, shipment_id: u64) -> Vec<Leg>;
+    fn get_dispute_ruling(env: Env, shipment_id: u64) -> Option<u32>;
+}
+
+// ── Contract ──────────────────────────────────────────────────────────────────
+
+#[contract]
+pub struct EscrowContract;
+
+#[contractimpl]
+impl EscrowContract {
+    // ── Setup ─────────────────────────────────────────────────────────────
+
+    /// One-time initialisation.  `token_contract` is the first SEP-41 token on
+    /// the settlement allowlist (e.g. the Stellar native-XLM wrapper contract
+    /// on Soroban); more can be added with `allow_token`.
+    pub fn initialize(
         env: Env,
-        shipment_id: u64,
-        release_to_carrier: bool,
+        admin: Address,
+        token_contract: Address,
     ) -> Result<(), EscrowError> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(EscrowError::AlreadyInitialized);
+        }
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(
+            &DataKey::AllowedTokens,
+            &Vec::from_array(&env, [token_contract]),
+        );
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        Ok(())
+    }
+
+    /// Admin adds a token shippers may fund escrows in.
+    pub fn allow_token(env: Env, token: Address) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut tokens = Self::get_allowed_tokens(env.clone());
+        if !tokens.contains(&token) {
+            tokens.push_back(token.clone());
+            env.storage()
+                .instance()
+                .set(&DataKey::AllowedTokens, &tokens);
+        }
+
+        emit(
+            &env,
+            symbol_short!("allowlist"),
+            AllowlistEvent {
+                token,
+                allowed: true,
+            },
+        );
+        Ok(())
+    }
+
+    /// Admin stops accepting new escrows in `token`.  Escrows already funded
+    /// in it still settle in it.
+    pub fn disallow_token(env: Env, token: Address) -> Result<(), EscrowError> {
         let admin: Address = env
             .storage()
             .instance()
@@ -267,258 +1102,6993 @@ impl EscrowContract {
             .ok_or(EscrowError::NotInitialized)?;
         admin.require_auth();
 
-        let mut record = Self::load(&env, shipment_id)?;
+        let mut tokens = Self::get_allowed_tokens(env.clone());
+        let index = tokens
+            .first_index_of(&token)
+            .ok_or(EscrowError::TokenNotAllowed)?;
+        tokens.remove(index);
+        env.storage()
+            .instance()
+            .set(&DataKey::AllowedTokens, &tokens);
+
+        emit(
+            &env,
+            symbol_short!("allowlist"),
+            AllowlistEvent {
+                token,
+                allowed: false,
+            },
+        );
+        Ok(())
+    }
+
+    // ── Shipper actions ───────────────────────────────────────────────────
+
+    /// Shipper locks funds for a shipment.
+    ///
+    /// **Pre-condition:** the shipper must have called `approve` on the token
+    /// contract, granting this escrow contract an allowance ≥ `amount`.
+    ///
+    /// The contract pulls the tokens from `shipper` via `transfer_from`.
+    /// `token` must be on the settlement allowlist.
+    pub fn fund_escrow(
+        env: Env,
+        shipper: Address,
+        carrier: Address,
+        shipment_id: u64,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        Self::open_escrow(
+            &env,
+            shipper.clone(),
+            shipper,
+            carrier,
+            shipment_id,
+            token,
+            amount,
+            Vec::new(&env),
+            true,
+            0,
+            Vec::new(&env),
+        )
+    }
+
+    /// Like `fund_escrow`, but a third party — typically the consignee or a
+    /// freight forwarder — pays.  Both `payer` and `shipper` authorize; the
+    /// tokens come from `payer`, who also receives any refund.  The shipper
+    /// still confirms delivery.
+    pub fn fund_escrow_for(
+        env: Env,
+        payer: Address,
+        shipper: Address,
+        carrier: Address,
+        shipment_id: u64,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        Self::open_escrow(
+            &env,
+            payer,
+            shipper,
+            carrier,
+            shipment_id,
+            token,
+            amount,
+            Vec::new(&env),
+            true,
+            0,
+            Vec::new(&env),
+        )
+    }
+
+    /// Shipper opens an escrow for `amount` without paying yet.  It stays
+    /// `Pending` until deposits cover the amount; `cancel_pending` gives up.
+    /// With a shipment contract linked, only that shipment's shipper may open
+    /// it, so nobody else can hold the shipment id with an empty escrow.
+    pub fn create_escrow(
+        env: Env,
+        shipper: Address,
+        carrier: Address,
+        shipment_id: u64,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        let contract: Option<Address> = env.storage().instance().get(&DataKey::ShipmentContract);
+        if let Some(contract) = contract {
+            let shipment =
+                match ShipmentContractClient::new(&env, &contract).try_get_shipment(&shipment_id) {
+                    Ok(Ok(shipment)) => shipment,
+                    _ => return Err(EscrowError::ShipmentNotSettled),
+                };
+            if shipment.shipper != shipper {
+                return Err(EscrowError::PartyMismatch);
+            }
+        }
+
+        Self::open_escrow(
+            &env,
+            shipper.clone(),
+            shipper,
+            carrier,
+            shipment_id,
+            token,
+            amount,
+            Vec::new(&env),
+            false,
+            0,
+            Vec::new(&env),
+        )
+    }
+
+    /// Anyone deposits towards an escrow, e.g. a shipper paying in instalments
+    /// or a consignee covering part of the price.  Requires a prior `approve`
+    /// like `fund_escrow`.  The escrow becomes `Funded` once deposits cover
+    /// `amount`.  Each depositor's share is kept, and refunds go back to the
+    /// depositors pro rata.
+    pub fn deposit(
+        env: Env,
+        payer: Address,
+        shipment_id: u64,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Funding)?;
+        payer.require_auth();
+
+        if amount <= 0 {
+            return Err(EscrowError::InvalidAmount);
+        }
+        let mut record = Self::load(&env, shipment_id)?;
+        if !matches!(record.status, EscrowStatus::Pending | EscrowStatus::Funded) {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        token::Client::new(&env, &record.token).transfer_from(
+            &env.current_contract_address(),
+            &payer,
+            &env.current_contract_address(),
+            &amount,
+        );
+        let mut deposits = Self::deposits(&env, &record);
+        deposits.set(
+            payer.clone(),
+            deposits.get(payer.clone()).unwrap_or(0) + amount,
+        );
+        Self::store_deposits(&env, shipment_id, &deposits);
+        record.deposited += amount;
+
+        emit(
+            &env,
+            symbol_short!("deposit"),
+            DepositEvent {
+                shipment_id,
+                payer,
+                amount,
+                deposited: record.deposited,
+            },
+        );
+        Self::update_funding(&env, &mut record);
+        Self::store(&env, &record);
+        Ok(())
+    }
+
+    /// Shipper and carrier agree a new price.  Raising it above what is on
+    /// deposit returns the escrow to `Pending` until topped up; lowering it
+    /// leaves the difference for `withdraw_excess`.
+    pub fn adjust_amount(env: Env, shipment_id: u64, new_amount: i128) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Funding)?;
+        let mut record = Self::load(&env, shipment_id)?;
+        record.shipper.require_auth();
+        record.carrier.require_auth();
+
+        if !matches!(record.status, EscrowStatus::Pending | EscrowStatus::Funded) {
+            return Err(EscrowError::InvalidStatus);
+        }
+        if new_amount <= 0 || new_amount < record.released_amount {
+            return Err(EscrowError::InvalidAmount);
+        }
+        if !record.legs.is_empty() {
+            return Err(EscrowError::PaidPerLeg);
+        }
+
+        record.amount = new_amount;
+        emit(
+            &env,
+            symbol_short!("adjusted"),
+            AdjustedEvent {
+                shipment_id,
+                amount: new_amount,
+                deposited: record.deposited,
+            },
+        );
+        Self::update_funding(&env, &mut record);
+        Self::store(&env, &record);
+        Ok(())
+    }
+
+    /// A depositor returns the deposits not backing the escrow: any
+    /// overpayment, or everything not yet paid out while the escrow is
+    /// `Pending`.  The excess goes back to every depositor pro rata.
+    /// Returns the amount withdrawn.
+    pub fn withdraw_excess(
+        env: Env,
+        payer: Address,
+        shipment_id: u64,
+    ) -> Result<i128, EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        payer.require_auth();
+
+        let mut record = Self::load(&env, shipment_id)?;
+        if record.payer != payer && !Self::deposits(&env, &record).contains_key(payer) {
+            return Err(EscrowError::Unauthorized);
+        }
+
+        let committed = if record.status == EscrowStatus::Pending {
+            record.released_amount
+        } else {
+            record.amount
+        };
+        let excess = record.deposited - committed;
+        if excess <= 0 {
+            return Err(EscrowError::NoExcess);
+        }
+
+        Self::return_deposits(&env, &mut record, excess, symbol_short!("withdrawn"));
+        Self::store(&env, &record);
+        Ok(excess)
+    }
+
+    /// Shipper gives up on a `Pending` escrow.  Deposits not yet paid out go
+    /// back to the depositors pro rata, and the shipment may be funded again
+    /// without the admin reopening it.
+    pub fn cancel_pending(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let record = Self::load(&env, shipment_id)?;
+        record.shipper.require_auth();
+
+        if record.status != EscrowStatus::Pending {
+            return Err(EscrowError::InvalidStatus);
+        }
+        Self::abandon(&env, record);
+        Ok(())
+    }
+
+    /// Shipper locks funds that are paid out in tranches.
+    ///
+    /// `schedule` is an ordered list of `(label, bps)` pairs — e.g.
+    /// `[(pickup, 2000), (border, 3000), (delivery, 5000)]` — whose shares must
+    /// sum to 10 000.  Each tranche is released independently via
+    /// `release_milestone`.
+    pub fn fund_escrow_with_milestones(
+        env: Env,
+        shipper: Address,
+        carrier: Address,
+        shipment_id: u64,
+        token: Address,
+        amount: i128,
+        schedule: Vec<(Symbol, u32)>,
+    ) -> Result<(), EscrowError> {
+        if schedule.is_empty() || schedule.len() > MAX_MILESTONES {
+            return Err(EscrowError::InvalidMilestones);
+        }
+
+        let mut milestones = Vec::new(&env);
+        let mut total: u32 = 0;
+        for (label, bps) in schedule.iter() {
+            if bps == 0 {
+                return Err(EscrowError::InvalidMilestones);
+            }
+            total = total.saturating_add(bps);
+            milestones.push_back(Milestone {
+                label,
+                bps,
+                released: false,
+            });
+        }
+        if total != BPS_DENOMINATOR {
+            return Err(EscrowError::InvalidMilestones);
+        }
+
+        Self::open_escrow(
+            &env,
+            shipper.clone(),
+            shipper,
+            carrier,
+            shipment_id,
+            token,
+            amount,
+            milestones,
+            true,
+            0,
+            Vec::new(&env),
+        )
+    }
+
+    /// Shipper locks funds that vest to the carrier linearly over
+    /// `transit_seconds` once the shipment is in transit (see
+    /// `start_stream`).  The carrier draws the vested part with
+    /// `withdraw_vested`; completion releases the rest as usual.
+    pub fn fund_escrow_streaming(
+        env: Env,
+        shipper: Address,
+        carrier: Address,
+        shipment_id: u64,
+        token: Address,
+        amount: i128,
+        transit_seconds: u64,
+    ) -> Result<(), EscrowError> {
+        if transit_seconds == 0 {
+            return Err(EscrowError::InvalidAmount);
+        }
+        Self::open_escrow(
+            &env,
+            shipper.clone(),
+            shipper,
+            carrier,
+            shipment_id,
+            token,
+            amount,
+            Vec::new(&env),
+            true,
+            transit_seconds,
+            Vec::new(&env),
+        )
+    }
+
+    /// Shipper locks the full price of a multi-leg shipment, read from the
+    /// linked shipment contract.  Each leg is paid to its own carrier through
+    /// `release_leg`; `carrier` is the first leg's carrier, who stands for
+    /// the escrow in disputes, bonds and payout assignment.  Every leg must
+    /// have been accepted: its carrier is fixed here.
+    pub fn fund_escrow_multileg(
+        env: Env,
+        shipper: Address,
+        carrier: Address,
+        shipment_id: u64,
+        token: Address,
+    ) -> Result<(), EscrowError> {
+        let contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::ShipmentContract)
+            .ok_or(EscrowError::ShipmentContractNotSet)?;
+        let shipments = ShipmentContractClient::new(&env, &contract);
+        let shipment = match shipments.try_get_shipment(&shipment_id) {
+            Ok(Ok(shipment)) => shipment,
+            _ => return Err(EscrowError::ShipmentNotSettled),
+        };
+        if shipment.shipper != shipper || shipment.carrier != Some(carrier.clone()) {
+            return Err(EscrowError::PartyMismatch);
+        }
+
+        let mut legs = Vec::new(&env);
+        let mut amount: i128 = 0;
+        for leg in shipments.get_legs(&shipment_id).iter() {
+            if leg.price <= 0 {
+                return Err(EscrowError::InvalidAmount);
+            }
+            amount = amount
+                .checked_add(leg.price)
+                .ok_or(EscrowError::InvalidAmount)?;
+            legs.push_back(LegPayout {
+                price: leg.price,
+                carrier: leg.carrier.ok_or(EscrowError::LegUnassigned)?,
+                paid_to: None,
+            });
+        }
+        match legs.first() {
+            None => return Err(EscrowError::LegNotFound),
+            Some(first) if first.carrier != carrier => return Err(EscrowError::PartyMismatch),
+            Some(_) => {}
+        }
+
+        Self::open_escrow(
+            &env,
+            shipper.clone(),
+            shipper,
+            carrier,
+            shipment_id,
+            token,
+            amount,
+            Vec::new(&env),
+            true,
+            0,
+            legs,
+        )
+    }
+
+    // ── Settlement ────────────────────────────────────────────────────────
+
+    /// Release locked funds to the carrier.
+    /// Called when a shipment is Completed (shipper confirmed delivery).
+    /// In production this would be called by an authorized shipment contract;
+    /// for now admin can also trigger it after off-chain verification.
+    ///
+    /// If a settlement delay is configured the release is only scheduled;
+    /// see `execute_settlement`.
+    pub fn release_payment(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        Self::require_single_admin(&env)?;
+        Self::do_release(&env, shipment_id)
+    }
+
+    /// Refund locked funds back to the payer.
+    /// Called when a shipment is Cancelled.  Timelocked like `release_payment`.
+    /// Only the unreleased remainder of a milestone escrow is returned, and a
+    /// streaming escrow first pays the carrier what has vested.
+    /// Once a cancellation fee table is configured this is `cancel_escrow`.
+    pub fn refund_payment(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        Self::require_single_admin(&env)?;
+        Self::do_refund(&env, shipment_id)
+    }
+
+    /// Cancel a funded escrow.  The payer is refunded except for the
+    /// cancellation fee for the stage the linked shipment reached, which
+    /// compensates the carrier.  Timelocked like `refund_payment`.
+    pub fn cancel_escrow(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        Self::require_single_admin(&env)?;
+        Self::do_cancel(&env, shipment_id)
+    }
+
+    /// Settle from the linked shipment contract's status, without an admin:
+    /// `Completed` releases to the carrier, `Cancelled` refunds the payer
+    /// (minus the Pending or Accepted cancellation fee, depending on whether
+    /// a carrier had accepted) and `PartiallyCompleted` splits per the
+    /// shipment's dispute ruling.  A multi-leg escrow whose legs were all
+    /// confirmed pays out through `release_leg` instead; when a ruling closed
+    /// the shipment, every unpaid leg settles here.  Anyone may call this; the
+    /// shipment's parties
+    /// must match the escrow's.  Not timelocked — the shipment contract has
+    /// already recorded the outcome.
+    pub fn settle_from_shipment(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::ShipmentContract)
+            .ok_or(EscrowError::ShipmentContractNotSet)?;
+        let record = Self::load(&env, shipment_id)?;
+
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let shipment =
+            match ShipmentContractClient::new(&env, &contract).try_get_shipment(&shipment_id) {
+                Ok(Ok(shipment)) => shipment,
+                _ => return Err(EscrowError::ShipmentNotSettled),
+            };
+        if shipment.shipper != record.shipper {
+            return Err(EscrowError::PartyMismatch);
+        }
+        if let Some(carrier) = &shipment.carrier {
+            if *carrier != record.carrier {
+                return Err(EscrowError::PartyMismatch);
+            }
+        }
+
+        let client = ShipmentContractClient::new(&env, &contract);
+        let kind = match (shipment.status, shipment.carrier) {
+            // Confirmed legs pay out through `release_leg`; a ruling that
+            // completed the shipment over unconfirmed legs releases them all.
+            (ShipmentStatus::Completed, Some(_))
+                if !record.legs.is_empty() && Self::legs_confirmed(&client, &record) =>
+            {
+                return Err(EscrowError::PaidPerLeg)
+            }
+            (ShipmentStatus::Completed, Some(_)) => SettlementKind::Release,
+            (ShipmentStatus::PartiallyCompleted, Some(_)) => {
+                let carrier_bps = match client.try_get_dispute_ruling(&shipment_id) {
+                    Ok(Ok(Some(carrier_bps))) => carrier_bps,
+                    _ => return Err(EscrowError::ShipmentNotSettled),
+                };
+                return Self::apply_ruling(
+                    &env,
+                    record,
+                    DisputeRuling {
+                        carrier_bps,
+                        fee_bps: 0,
+                        fee_recipient: None,
+                        slash_bps: 0,
+                    },
+                );
+            }
+            (ShipmentStatus::Cancelled, None) => SettlementKind::Cancel(CancelStage::Pending),
+            (ShipmentStatus::Cancelled, Some(_)) => SettlementKind::Cancel(CancelStage::Accepted),
+            _ => return Err(EscrowError::ShipmentNotSettled),
+        };
+        Self::settle(&env, record, kind);
+        Ok(())
+    }
+
+    /// Carrier claims the escrow when the shipper has not confirmed delivery
+    /// within the confirmation window.  The window starts when the linked
+    /// shipment reaches `Delivered`; a dispute raised on either contract
+    /// before then blocks the claim.
+    pub fn claim_unconfirmed(
+        env: Env,
+        carrier: Address,
+        shipment_id: u64,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        carrier.require_auth();
+
+        let window: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::ConfirmationWindow)
+            .unwrap_or(0);
+        if window == 0 {
+            return Err(EscrowError::AutoReleaseDisabled);
+        }
+        let contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::ShipmentContract)
+            .ok_or(EscrowError::ShipmentContractNotSet)?;
+        let record = Self::load(&env, shipment_id)?;
+
+        if record.carrier != carrier {
+            return Err(EscrowError::Unauthorized);
+        }
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+        if !record.legs.is_empty() {
+            return Err(EscrowError::PaidPerLeg);
+        }
+
+        let shipment =
+            match ShipmentContractClient::new(&env, &contract).try_get_shipment(&shipment_id) {
+                Ok(Ok(shipment)) => shipment,
+                _ => return Err(EscrowError::ShipmentNotSettled),
+            };
+        if shipment.shipper != record.shipper || shipment.carrier != Some(carrier) {
+            return Err(EscrowError::PartyMismatch);
+        }
+        if shipment.status != ShipmentStatus::Delivered {
+            return Err(EscrowError::ShipmentNotSettled);
+        }
+        // Shipments delivered before the shipment contract recorded
+        // `delivered_at` fall back to `updated_at`, which was the delivery time.
+        let delivered_at = if shipment.delivered_at != 0 {
+            shipment.delivered_at
+        } else {
+            shipment.updated_at
+        };
+        if env.ledger().timestamp() < delivered_at.saturating_add(window) {
+            return Err(EscrowError::DeadlineNotReached);
+        }
+
+        Self::settle(&env, record, SettlementKind::Release);
+        Ok(())
+    }
+
+    /// Shipper reclaims the escrow when no carrier accepted by `accept_by`, or
+    /// the cargo was not picked up by `pickup_by`, according to the linked
+    /// shipment contract.  Refunds in full without the admin.
+    pub fn reclaim_expired(
+        env: Env,
+        shipper: Address,
+        shipment_id: u64,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        shipper.require_auth();
+
+        let contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::ShipmentContract)
+            .ok_or(EscrowError::ShipmentContractNotSet)?;
+        let record = Self::load(&env, shipment_id)?;
+
+        if record.shipper != shipper {
+            return Err(EscrowError::Unauthorized);
+        }
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+        if record.accept_by == 0 && record.pickup_by == 0 {
+            return Err(EscrowError::NoDeadlineSet);
+        }
+
+        let shipment =
+            match ShipmentContractClient::new(&env, &contract).try_get_shipment(&shipment_id) {
+                Ok(Ok(shipment)) => shipment,
+                _ => return Err(EscrowError::ShipmentNotSettled),
+            };
+        if shipment.shipper != shipper {
+            return Err(EscrowError::PartyMismatch);
+        }
+
+        let now = env.ledger().timestamp();
+        let missed = |deadline: u64| deadline != 0 && now > deadline;
+        let expired = match shipment.status {
+            ShipmentStatus::Created => missed(record.accept_by) || missed(record.pickup_by),
+            ShipmentStatus::Accepted => missed(record.pickup_by),
+            _ => false,
+        };
+        if !expired {
+            return Err(EscrowError::DeadlineNotReached);
+        }
+
+        Self::settle(&env, record, SettlementKind::Refund);
+        Ok(())
+    }
+
+    /// Release one tranche of a milestone escrow to the carrier.
+    /// Timelocked like `release_payment`.
+    pub fn release_milestone(env: Env, shipment_id: u64, index: u32) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        Self::require_single_admin(&env)?;
+        Self::do_release_milestone(&env, shipment_id, index)
+    }
+
+    /// Execute a scheduled release or refund once its challenge window has
+    /// elapsed.  Anyone may call this.
+    pub fn execute_settlement(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let record = Self::load(&env, shipment_id)?;
+
+        let kind = match &record.status {
+            EscrowStatus::Scheduled(kind) => kind.clone(),
+            _ => return Err(EscrowError::InvalidStatus),
+        };
+        if env.ledger().timestamp() < record.execute_after {
+            return Err(EscrowError::TimelockActive);
+        }
+
+        Self::settle(&env, record, kind);
+        Ok(())
+    }
+
+    /// Raise a dispute for the escrow (mirrors the shipment dispute).
+    /// Either party can call this; admin then resolves via release or refund.
+    /// Raising it during a challenge window cancels the scheduled settlement.
+    pub fn raise_dispute(env: Env, caller: Address, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::All)?;
+        caller.require_auth();
+
+        let mut record = Self::load(&env, shipment_id)?;
+
+        let is_party = record.shipper == caller || record.carrier == caller;
+        if !is_party {
+            return Err(EscrowError::Unauthorized);
+        }
+        if !matches!(
+            record.status,
+            EscrowStatus::Funded | EscrowStatus::Scheduled(_)
+        ) {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        record.status = EscrowStatus::Disputed;
+        record.execute_after = 0;
+        record.commission = CommissionTerms::Uncharged;
+        if let Some(start) = Self::stream_start(&env, &record) {
+            record.stream.started_at = Some(start);
+            record.stream.frozen_at = Some(env.ledger().timestamp());
+        }
+        Self::store(&env, &record);
+
+        emit(
+            &env,
+            symbol_short!("disputed"),
+            DisputedEvent {
+                shipment_id,
+                raised_by: caller,
+            },
+        );
+        Ok(())
+    }
+
+    /// Admin resolves a disputed escrow.
+    /// `release_to_carrier = true` → funds go to carrier.
+    /// `release_to_carrier = false` → funds returned to payer.
+    pub fn resolve_dispute(
+        env: Env,
+        shipment_id: u64,
+        release_to_carrier: bool,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        Self::require_single_admin(&env)?;
+        Self::do_resolve(&env, shipment_id, Self::all_or_nothing(release_to_carrier))
+    }
+
+    /// Admin resolves a disputed escrow with a proportional split.
+    ///
+    /// The arbitration fee (`fee_bps` of the held funds) goes to the
+    /// arbitration treasury; the rest is divided `carrier_bps` / `10 000 -
+    /// carrier_bps` between carrier and shipper.  `slash_bps` of the
+    /// carrier's posted bond goes to the shipper.  A streaming escrow first
+    /// pays the carrier what vested before the dispute; only the rest is
+    /// held.  All transfers settle in the same call and the outcome is
+    /// recorded on the escrow.  A ruling
+    /// that takes a fee needs the signers: it is only accepted as an
+    /// `EscrowAction::ResolveSplit` proposal.
+    pub fn resolve_dispute_split(
+        env: Env,
+        shipment_id: u64,
+        ruling: DisputeRuling,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        Self::require_single_admin(&env)?;
+        if ruling.fee_bps > 0 {
+            return Err(EscrowError::MultisigRequired);
+        }
+        Self::do_resolve(&env, shipment_id, ruling)
+    }
+
+    // ── Batch settlement ──────────────────────────────────────────────────
+
+    /// `release_payment` for each of `shipment_ids`.  Ineligible shipments are
+    /// reported in the result, in input order, instead of failing the batch.
+    pub fn release_batch(
+        env: Env,
+        shipment_ids: Vec<u64>,
+    ) -> Result<Vec<BatchResult>, EscrowError> {
+        Self::settle_batch(&env, shipment_ids, Self::do_release)
+    }
+
+    /// `refund_payment` for each of `shipment_ids`, reported like
+    /// `release_batch`.
+    pub fn refund_batch(env: Env, shipment_ids: Vec<u64>) -> Result<Vec<BatchResult>, EscrowError> {
+        Self::settle_batch(&env, shipment_ids, Self::do_refund)
+    }
+
+    // ── History ───────────────────────────────────────────────────────────
+
+    /// Admin allows a settled shipment to be funded again.  The settled
+    /// record is archived as-is when the next round is funded.  A `Pending`
+    /// escrow is closed first, its deposits returned as by `cancel_pending`.
+    pub fn reopen_escrow(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        Self::require_single_admin(&env)?;
+        Self::do_reopen(&env, shipment_id)
+    }
+
+    // ── Timelock ──────────────────────────────────────────────────────────
+
+    /// Admin sets the challenge window, in seconds, between scheduling a
+    /// release/refund and executing it.  `0` settles immediately.
+    ///
+    /// A longer window applies at once.  A shorter one only takes effect
+    /// after the current window has run, so a compromised admin key cannot
+    /// drop the window and settle straight away.
+    pub fn set_settlement_delay(env: Env, delay_seconds: u64) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        let current = Self::get_settlement_delay(env.clone());
+        env.storage().instance().remove(&DataKey::PendingDelay);
+        if delay_seconds >= current {
+            env.storage()
+                .instance()
+                .set(&DataKey::SettlementDelay, &delay_seconds);
+            emit(&env, symbol_short!("delay"), DelayEvent { delay_seconds });
+            return Ok(());
+        }
+
+        let pending = PendingDelay {
+            delay_seconds,
+            effective_at: env.ledger().timestamp() + current,
+        };
+        env.storage()
+            .instance()
+            .set(&DataKey::SettlementDelay, &current);
+        env.storage()
+            .instance()
+            .set(&DataKey::PendingDelay, &pending);
+        emit(&env, symbol_short!("delay_low"), pending);
+        Ok(())
+    }
+
+    // ── Shipment link ─────────────────────────────────────────────────────
+
+    /// Admin links the shipment contract `settle_from_shipment` reads from.
+    /// Once signers are configured this is
+    /// `EscrowAction::SetShipmentContract`.
+    pub fn set_shipment_contract(env: Env, contract: Address) -> Result<(), EscrowError> {
+        Self::require_single_admin(&env)?;
+        Self::do_set_shipment_contract(&env, contract);
+        Ok(())
+    }
+
+    /// Admin sets how long, in seconds, a shipper has to confirm delivery
+    /// before the carrier may `claim_unconfirmed`.  `0` disables the claim.
+    pub fn set_confirmation_window(env: Env, window_seconds: u64) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        env.storage()
+            .instance()
+            .set(&DataKey::ConfirmationWindow, &window_seconds);
+
+        emit(
+            &env,
+            symbol_short!("window"),
+            WindowEvent { window_seconds },
+        );
+        Ok(())
+    }
+
+    /// Admin sets the acceptance and pickup deadlines stamped onto escrows
+    /// funded from now on.  Existing escrows keep theirs.
+    pub fn set_deadline_policy(env: Env, policy: DeadlinePolicy) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        env.storage()
+            .instance()
+            .set(&DataKey::DeadlinePolicy, &policy);
+
+        emit(&env, symbol_short!("deadlines"), policy);
+        Ok(())
+    }
+
+    // ── Cancellation fees ─────────────────────────────────────────────────
+
+    /// Admin sets the cancellation fee table used by `cancel_escrow` and
+    /// `refund_payment`.
+    pub fn set_cancellation_fees(env: Env, fees: CancellationFees) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        if fees.pending > BPS_DENOMINATOR
+            || fees.accepted > BPS_DENOMINATOR
+            || fees.in_transit > BPS_DENOMINATOR
+        {
+            return Err(EscrowError::InvalidFeeTable);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::CancellationFees, &fees);
+
+        emit(&env, symbol_short!("cancelfee"), fees);
+        Ok(())
+    }
+
+    // ── Multisig ──────────────────────────────────────────────────────────
+
+    /// Admin configures the initial N-of-M signer set.  `threshold` must be
+    /// within `1..=signers.len()` and the signers must be distinct.
+    ///
+    /// Once a signer set exists, `release_payment`, `refund_payment` and
+    /// `resolve_dispute` reject with `MultisigRequired`; settlement has to go
+    /// through `propose_action` → `approve_action` → `execute_proposal`.
+    /// So does this call: later changes are `EscrowAction::SetSigners`
+    /// proposals.
+    pub fn set_signers(env: Env, signers: Vec<Address>, threshold: u32) -> Result<(), EscrowError> {
+        Self::require_single_admin(&env)?;
+        Self::do_set_signers(&env, signers, threshold)
+    }
+
+    /// A signer proposes a settlement action for `shipment_id`.
+    /// The proposer's approval is counted immediately.  Returns the proposal id.
+    pub fn propose_action(
+        env: Env,
+        proposer: Address,
+        shipment_id: u64,
+        action: EscrowAction,
+    ) -> Result<u64, EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        proposer.require_auth();
+        Self::require_signer(&env, &proposer)?;
+
+        // Reject proposals for escrows that do not exist up front.
+        let round = if action.is_contract_wide() {
+            0
+        } else {
+            Self::load(&env, shipment_id)?.round
+        };
+
+        let id = Self::next_proposal_id(&env);
+        let mut approvals = Vec::new(&env);
+        approvals.push_back(proposer.clone());
+
+        let now = env.ledger().timestamp();
+        let proposal = Proposal {
+            id,
+            shipment_id,
+            action,
+            proposer: proposer.clone(),
+            round,
+            approvals,
+            executed: false,
+            created_at: now,
+            expires_at: now + PROPOSAL_LIFETIME,
+            executed_at: 0,
+        };
+        Self::store_proposal(&env, &proposal);
+
+        emit(
+            &env,
+            symbol_short!("proposed"),
+            ProposalEvent {
+                proposal_id: id,
+                shipment_id,
+                signer: proposer,
+            },
+        );
+        Ok(id)
+    }
+
+    /// A signer approves an open proposal.
+    pub fn approve_action(env: Env, signer: Address, proposal_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        signer.require_auth();
+        Self::require_signer(&env, &signer)?;
+
+        let mut proposal = Self::load_proposal(&env, proposal_id)?;
+
+        if proposal.executed {
+            return Err(EscrowError::ProposalExecuted);
+        }
+        if env.ledger().timestamp() > proposal.expires_at {
+            return Err(EscrowError::ProposalNotFound);
+        }
+        if proposal.approvals.contains(&signer) {
+            return Err(EscrowError::AlreadyApproved);
+        }
+
+        proposal.approvals.push_back(signer.clone());
+        Self::store_proposal(&env, &proposal);
+
+        emit(
+            &env,
+            symbol_short!("approved"),
+            ProposalEvent {
+                proposal_id,
+                shipment_id: proposal.shipment_id,
+                signer,
+            },
+        );
+        Ok(())
+    }
+
+    /// Execute a proposal once it has reached the threshold.
+    /// Anyone may submit this — the approvals are the authorisation.
+    /// Only approvals from the *current* signer set are counted, and only
+    /// against the escrow round they were given for.
+    pub fn execute_proposal(env: Env, proposal_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let mut proposal = Self::load_proposal(&env, proposal_id)?;
+
+        if proposal.executed {
+            return Err(EscrowError::ProposalExecuted);
+        }
+        if env.ledger().timestamp() > proposal.expires_at {
+            return Err(EscrowError::ProposalNotFound);
+        }
+        if !proposal.action.is_contract_wide()
+            && Self::load(&env, proposal.shipment_id)?.round != proposal.round
+        {
+            // The escrow was funded again since the approvals were given.
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let signers = Self::get_signers(env.clone());
+        let threshold = Self::get_threshold(env.clone());
+        let valid = proposal
+            .approvals
+            .iter()
+            .filter(|a| signers.contains(a))
+            .count() as u32;
+        if threshold == 0 || valid < threshold {
+            return Err(EscrowError::ThresholdNotMet);
+        }
+
+        match proposal.action.clone() {
+            EscrowAction::Release => Self::do_release(&env, proposal.shipment_id)?,
+            EscrowAction::Refund => Self::do_refund(&env, proposal.shipment_id)?,
+            EscrowAction::Cancel => Self::do_cancel(&env, proposal.shipment_id)?,
+            EscrowAction::ResolveDispute(release_to_carrier) => Self::do_resolve(
+                &env,
+                proposal.shipment_id,
+                Self::all_or_nothing(release_to_carrier),
+            )?,
+            EscrowAction::ResolveSplit(ruling) => {
+                Self::do_resolve(&env, proposal.shipment_id, ruling)?
+            }
+            EscrowAction::ReleaseMilestone(index) => {
+                Self::do_release_milestone(&env, proposal.shipment_id, index)?
+            }
+            EscrowAction::Reopen => Self::do_reopen(&env, proposal.shipment_id)?,
+            EscrowAction::SetSigners(signers, threshold) => {
+                Self::do_set_signers(&env, signers, threshold)?
+            }
+            EscrowAction::SweepSurplus(token, to, amount) => {
+                Self::do_sweep(&env, token, to, amount)?
+            }
+            EscrowAction::SetShipmentContract(contract) => {
+                Self::do_set_shipment_contract(&env, contract)
+            }
+            EscrowAction::SetArbitrationTreasury(treasury) => {
+                Self::do_set_arbitration_treasury(&env, treasury)
+            }
+            EscrowAction::SetCommission(bps, treasury, charge_on_refund) => {
+                Self::do_set_commission(&env, bps, treasury, charge_on_refund)?
+            }
+            EscrowAction::SetShipperCommission(shipper, bps) => {
+                Self::do_set_shipper_commission(&env, shipper, bps)?
+            }
+            EscrowAction::Upgrade(wasm_hash) => Self::do_upgrade(&env, wasm_hash),
+            EscrowAction::Migrate(shipment_ids, complete) => {
+                Self::do_migrate(&env, shipment_ids, complete);
+            }
+        }
+
+        proposal.executed = true;
+        proposal.executed_at = env.ledger().timestamp();
+        Self::store_proposal(&env, &proposal);
+
+        emit(
+            &env,
+            symbol_short!("executed"),
+            ExecutedEvent {
+                proposal_id,
+                shipment_id: proposal.shipment_id,
+                action: proposal.action,
+            },
+        );
+        Ok(())
+    }
+
+    // ── Commission ────────────────────────────────────────────────────────
+
+    /// Admin sets the platform commission deducted from releases (and, if
+    /// `charge_on_refund`, refunds) and paid to `treasury`.  Dispute rulings
+    /// are not charged; they carry their own arbitration fee.  Settlements
+    /// already scheduled keep the terms they were scheduled under.  Once
+    /// signers are configured this is `EscrowAction::SetCommission`.
+    pub fn set_commission(
+        env: Env,
+        bps: u32,
+        treasury: Address,
+        charge_on_refund: bool,
+    ) -> Result<(), EscrowError> {
+        Self::require_single_admin(&env)?;
+        Self::do_set_commission(&env, bps, treasury, charge_on_refund)
+    }
+
+    /// Admin overrides the commission rate for one shipper; `None` reverts
+    /// them to the default.  Once signers are configured this is
+    /// `EscrowAction::SetShipperCommission`.
+    pub fn set_shipper_commission(
+        env: Env,
+        shipper: Address,
+        bps: Option<u32>,
+    ) -> Result<(), EscrowError> {
+        Self::require_single_admin(&env)?;
+        Self::do_set_shipper_commission(&env, shipper, bps)
+    }
+
+    /// Admin sets where dispute rulings send their arbitration fee.  Once
+    /// signers are configured this is `EscrowAction::SetArbitrationTreasury`.
+    pub fn set_arbitration_treasury(env: Env, treasury: Address) -> Result<(), EscrowError> {
+        Self::require_single_admin(&env)?;
+        Self::do_set_arbitration_treasury(&env, treasury);
+        Ok(())
+    }
+
+    // ── Solvency ──────────────────────────────────────────────────────────
+
+    /// Admin moves `amount` of `token` the contract holds beyond what it owes
+    /// escrows, e.g. tokens sent to it by mistake.  Escrowed funds can never
+    /// be swept.  Once signers are configured this is
+    /// `EscrowAction::SweepSurplus`.
+    pub fn sweep_surplus(
+        env: Env,
+        token: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        Self::require_single_admin(&env)?;
+        Self::do_sweep(&env, token, to, amount)
+    }
+
+    // ── Carrier bonds ─────────────────────────────────────────────────────
+
+    /// Carrier adds to its standing bond in `token`.  Requires a prior
+    /// `approve` like `fund_escrow`.
+    pub fn deposit_bond(
+        env: Env,
+        carrier: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Funding)?;
+        carrier.require_auth();
+        if amount <= 0 {
+            return Err(EscrowError::InvalidAmount);
+        }
+        if !Self::get_allowed_tokens(env.clone()).contains(&token) {
+            return Err(EscrowError::TokenNotAllowed);
+        }
+
+        let mut account = Self::get_bond(env.clone(), carrier.clone(), token.clone());
+        Self::pull_bond(&env, &carrier, &token, amount, &mut account);
+        Self::store_bond(&env, &carrier, &token, &account);
+        Ok(())
+    }
+
+    /// Carrier stakes `amount` on an open escrow it carries, in the escrow's
+    /// token.  Free standing bond is used first; any shortfall is pulled from
+    /// the carrier's wallet.  Freed when the escrow settles, unless a dispute
+    /// ruling slashes it.
+    pub fn post_bond(
+        env: Env,
+        carrier: Address,
+        shipment_id: u64,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Funding)?;
+        carrier.require_auth();
+        if amount <= 0 {
+            return Err(EscrowError::InvalidAmount);
+        }
+
+        let mut record = Self::load(&env, shipment_id)?;
+        if record.carrier != carrier {
+            return Err(EscrowError::Unauthorized);
+        }
+        if !matches!(record.status, EscrowStatus::Pending | EscrowStatus::Funded) {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let token = record.token.clone();
+        let mut account = Self::get_bond(env.clone(), carrier.clone(), token.clone());
+        let free = account.balance - account.committed - account.withdrawing;
+        if free < amount {
+            Self::pull_bond(&env, &carrier, &token, amount - free, &mut account);
+        }
+        account.committed += amount;
+        record.bond += amount;
+        Self::store_bond(&env, &carrier, &token, &account);
+        Self::store(&env, &record);
+
+        emit(
+            &env,
+            symbol_short!("bond_post"),
+            BondPostEvent {
+                shipment_id,
+                carrier,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Carrier asks to withdraw `amount` of its free bond, claimable with
+    /// `withdraw_bond` once the cooldown has elapsed.  Replaces any earlier
+    /// request and restarts the cooldown.  Frozen with settlement, like
+    /// `withdraw_bond`.
+    pub fn request_bond_withdrawal(
+        env: Env,
+        carrier: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        carrier.require_auth();
+        if amount <= 0 {
+            return Err(EscrowError::InvalidAmount);
+        }
+
+        let mut account = Self::get_bond(env.clone(), carrier.clone(), token.clone());
+        if amount > account.balance - account.committed {
+            return Err(EscrowError::InsufficientBond);
+        }
+        account.withdrawing = amount;
+        account.unlock_at = env.ledger().timestamp() + Self::get_bond_cooldown(env.clone());
+        Self::store_bond(&env, &carrier, &token, &account);
+
+        emit(
+            &env,
+            symbol_short!("bond_req"),
+            BondWithdrawalEvent {
+                carrier,
+                token,
+                amount,
+                unlock_at: account.unlock_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// Carrier claims its requested bond withdrawal and returns the amount
+    /// paid, which slashing since the request may have reduced.
+    pub fn withdraw_bond(env: Env, carrier: Address, token: Address) -> Result<i128, EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        carrier.require_auth();
+
+        let mut account = Self::get_bond(env.clone(), carrier.clone(), token.clone());
+        if account.withdrawing == 0 {
+            return Err(EscrowError::NoWithdrawalPending);
+        }
+        if env.ledger().timestamp() < account.unlock_at {
+            return Err(EscrowError::BondLocked);
+        }
+
+        let amount = account
+            .withdrawing
+            .min(account.balance - account.committed)
+            .max(0);
+        account.balance -= amount;
+        account.withdrawing = 0;
+        account.unlock_at = 0;
+        Self::store_bond(&env, &carrier, &token, &account);
+        Self::add_total(&env, &DataKey::Liabilities(token.clone()), -amount);
+        if amount > 0 {
+            token::Client::new(&env, &token).transfer(
+                &env.current_contract_address(),
+                &carrier,
+                &amount,
+            );
+        }
+
+        emit(
+            &env,
+            symbol_short!("bond_out"),
+            BondEvent {
+                carrier,
+                token,
+                amount,
+                balance: account.balance,
+            },
+        );
+        Ok(amount)
+    }
+
+    /// Admin sets the wait, in seconds, between requesting and withdrawing a
+    /// bond.
+    pub fn set_bond_cooldown(env: Env, delay_seconds: u64) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        env.storage()
+            .instance()
+            .set(&DataKey::BondCooldown, &delay_seconds);
+
+        emit(
+            &env,
+            symbol_short!("bondcool"),
+            DelayEvent { delay_seconds },
+        );
+        Ok(())
+    }
+
+    // ── Streaming ─────────────────────────────────────────────────────────
+
+    /// Pin the start of a streaming escrow's vesting to the moment the linked
+    /// shipment went in transit.  Optional: vesting already runs from the
+    /// shipment's pickup, and the first draw or dispute pins it too.  Anyone
+    /// may call this, unless settlement is paused.
+    pub fn start_stream(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::ShipmentContract)
+            .ok_or(EscrowError::ShipmentContractNotSet)?;
+        let mut record = Self::load(&env, shipment_id)?;
+
+        if record.stream.duration == 0 {
+            return Err(EscrowError::NotStreaming);
+        }
+        if record.status != EscrowStatus::Funded || record.stream.started_at.is_some() {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let shipment =
+            match ShipmentContractClient::new(&env, &contract).try_get_shipment(&shipment_id) {
+                Ok(Ok(shipment)) => shipment,
+                _ => return Err(EscrowError::StreamNotStarted),
+            };
+        if shipment.shipper != record.shipper || shipment.carrier != Some(record.carrier.clone()) {
+            return Err(EscrowError::PartyMismatch);
+        }
+        if shipment.picked_up_at == 0 {
+            return Err(EscrowError::StreamNotStarted);
+        }
+
+        record.stream.started_at = Some(shipment.picked_up_at);
+        Self::store(&env, &record);
+
+        emit(
+            &env,
+            symbol_short!("stream_on"),
+            StreamStartEvent {
+                shipment_id,
+                started_at: shipment.picked_up_at,
+                duration: record.stream.duration,
+            },
+        );
+        Ok(())
+    }
+
+    /// The payee (the carrier or its assignee) draws everything vested so far
+    /// and returns the net amount paid.
+    pub fn withdraw_vested(env: Env, shipment_id: u64) -> Result<i128, EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let mut record = Self::load(&env, shipment_id)?;
+        let payee = Self::payee(&record);
+        payee.require_auth();
+
+        if record.stream.duration == 0 {
+            return Err(EscrowError::NotStreaming);
+        }
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+        if Self::stream_start(&env, &record).is_none() {
+            return Err(EscrowError::StreamNotStarted);
+        }
+        if Self::vested(&env, &record) <= record.released_amount {
+            return Err(EscrowError::NothingVested);
+        }
+
+        let token = token::Client::new(&env, &record.token);
+        let paid = Self::pay_vested(&env, &token, &mut record);
+        Self::store(&env, &record);
+        Ok(paid)
+    }
+
+    /// Vested but not yet withdrawn on a streaming escrow, before commission.
+    pub fn get_vested(env: Env, shipment_id: u64) -> Result<i128, EscrowError> {
+        let record = Self::load(&env, shipment_id)?;
+        if record.stream.duration == 0 {
+            return Err(EscrowError::NotStreaming);
+        }
+        Ok((Self::vested(&env, &record) - record.released_amount).max(0))
+    }
+
+    // ── Legs ──────────────────────────────────────────────────────────────
+
+    /// Pay leg `index` of a multi-leg escrow to that leg's carrier once the
+    /// shipper has confirmed it on the linked shipment contract.  Paying the
+    /// last outstanding leg releases the escrow.  Anyone may call this; not
+    /// timelocked, like `settle_from_shipment`.
+    pub fn release_leg(env: Env, shipment_id: u64, index: u32) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::ShipmentContract)
+            .ok_or(EscrowError::ShipmentContractNotSet)?;
+        let record = Self::load(&env, shipment_id)?;
+
+        let payout = record.legs.get(index).ok_or(EscrowError::LegNotFound)?;
+        if payout.paid_to.is_some() {
+            return Err(EscrowError::LegAlreadyPaid);
+        }
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let shipments = ShipmentContractClient::new(&env, &contract);
+        let shipment = match shipments.try_get_shipment(&shipment_id) {
+            Ok(Ok(shipment)) => shipment,
+            _ => return Err(EscrowError::ShipmentNotSettled),
+        };
+        if shipment.shipper != record.shipper {
+            return Err(EscrowError::PartyMismatch);
+        }
+        let leg = shipments
+            .get_legs(&shipment_id)
+            .get(index)
+            .ok_or(EscrowError::LegNotFound)?;
+        if leg.status != ShipmentStatus::Completed {
+            return Err(EscrowError::LegNotConfirmed);
+        }
+        let carrier = leg.carrier.ok_or(EscrowError::LegNotConfirmed)?;
+        if carrier != payout.carrier {
+            return Err(EscrowError::PartyMismatch);
+        }
+
+        Self::settle_leg(&env, record, index, carrier);
+        Ok(())
+    }
+
+    // ── Payout assignment ─────────────────────────────────────────────────
+
+    /// The current payee (the carrier, or its latest assignee) sells the
+    /// escrow's carrier payouts to `to`, who must consent.  Not once the
+    /// escrow is scheduled or settled, nor while settlement is paused.
+    pub fn assign_payout(
+        env: Env,
+        from: Address,
+        shipment_id: u64,
+        to: Address,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        from.require_auth();
+        to.require_auth();
+
+        let mut record = Self::load(&env, shipment_id)?;
+        if Self::payee(&record) != from {
+            return Err(EscrowError::Unauthorized);
+        }
+        Self::require_assignable(&record)?;
+
+        record.assignees.push_back(to.clone());
+        Self::store(&env, &record);
+
+        emit(
+            &env,
+            symbol_short!("assigned"),
+            AssignedEvent {
+                shipment_id,
+                from,
+                to,
+            },
+        );
+        Ok(())
+    }
+
+    /// The latest assignee hands the payout back to whoever assigned it.
+    /// Not once the escrow is scheduled or settled, nor while settlement is
+    /// paused.
+    pub fn revoke_assignment(env: Env, shipment_id: u64) -> Result<(), EscrowError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let mut record = Self::load(&env, shipment_id)?;
+        let assignee = record
+            .assignees
+            .pop_back()
+            .ok_or(EscrowError::NotAssigned)?;
+        assignee.require_auth();
+        Self::require_assignable(&record)?;
+
+        Self::store(&env, &record);
+
+        emit(
+            &env,
+            symbol_short!("revoked"),
+            AssignedEvent {
+                shipment_id,
+                from: assignee,
+                to: Self::payee(&record),
+            },
+        );
+        Ok(())
+    }
+
+    // ── Circuit breaker ───────────────────────────────────────────────────
+
+    /// Admin appoints the pauser — the key allowed to trip the breaker.
+    pub fn set_pauser(env: Env, pauser: Address) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Pauser, &pauser);
+
+        emit(&env, symbol_short!("pauser"), PauserEvent { pauser });
+        Ok(())
+    }
+
+    /// Pauser (or admin) freezes the entry points in `scope`.
+    /// Read methods keep working.
+    pub fn pause(env: Env, caller: Address, scope: PauseScope) -> Result<(), EscrowError> {
+        caller.require_auth();
+
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        let pauser: Option<Address> = env.storage().instance().get(&DataKey::Pauser);
+        if caller != admin && pauser.as_ref() != Some(&caller) {
+            return Err(EscrowError::Unauthorized);
+        }
+
+        let mut state = Self::get_pause_state(env.clone());
+        match scope {
+            PauseScope::Funding => state.funding = true,
+            PauseScope::Settlement => state.settlement = true,
+            PauseScope::All => {
+                state.funding = true;
+                state.settlement = true;
+            }
+        }
+        env.storage().instance().set(&DataKey::PauseState, &state);
+
+        emit(
+            &env,
+            symbol_short!("paused"),
+            PauseEvent { scope, by: caller },
+        );
+        Ok(())
+    }
+
+    /// Admin-only: lift the freeze on `scope`.
+    pub fn unpause(env: Env, scope: PauseScope) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut state = Self::get_pause_state(env.clone());
+        match scope {
+            PauseScope::Funding => state.funding = false,
+            PauseScope::Settlement => state.settlement = false,
+            PauseScope::All => state = PauseState::default(),
+        }
+        env.storage().instance().set(&DataKey::PauseState, &state);
+
+        emit(
+            &env,
+            symbol_short!("unpaused"),
+            PauseEvent { scope, by: admin },
+        );
+        Ok(())
+    }
+
+    // ── Admin ─────────────────────────────────────────────────────────────
+
+    /// Admin nominates a successor, who must `accept_admin` within
+    /// `ADMIN_TRANSFER_WINDOW`.  Replaces any earlier nomination.
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), EscrowError> {
+        Ok(ADMIN_TRANSFER.propose(&env, new_admin)?)
+    }
+
+    /// The nominated admin takes over.  Must be signed by them.
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), EscrowError> {
+        Ok(ADMIN_TRANSFER.accept(&env, new_admin)?)
+    }
+
+    /// Admin withdraws a pending nomination.
+    pub fn cancel_admin_transfer(env: Env) -> Result<(), EscrowError> {
+        Ok(ADMIN_TRANSFER.cancel(&env)?)
+    }
+
+    // ── Upgrades ──────────────────────────────────────────────────────────
+
+    /// Admin swaps in new contract code; storage is kept.  Run `migrate`
+    /// afterwards when the new code's `SCHEMA_VERSION` is higher.  Once
+    /// signers are configured this is `EscrowAction::Upgrade`.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), EscrowError> {
+        Self::require_single_admin(&env)?;
+        Self::do_upgrade(&env, new_wasm_hash);
+        Ok(())
+    }
+
+    /// Admin rewrites the given escrows in the current layout and returns how
+    /// many existed.  Schema 1's single token joins the allowlist until the
+    /// marker moves.  Old records are also upgraded on read, so batches can
+    /// run while the contract is live; pass `complete` with the last batch to
+    /// move the schema marker.  Schema 1's token key is kept: escrows are not
+    /// enumerable, so a record left out of every batch still needs it to
+    /// upgrade on read.  Once signers are configured this is
+    /// `EscrowAction::Migrate`.
+    pub fn migrate(env: Env, shipment_ids: Vec<u64>, complete: bool) -> Result<u32, EscrowError> {
+        Self::require_single_admin(&env)?;
+        Ok(Self::do_migrate(&env, shipment_ids, complete))
+    }
+
+    // ── Queries ───────────────────────────────────────────────────────────
+
+    pub fn version(_env: Env) -> u32 {
+        CONTRACT_VERSION
+    }
+
+    /// Storage layout the stored records are known to be in.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(1)
+    }
+
+    pub fn get_escrow(env: Env, shipment_id: u64) -> Result<EscrowRecord, EscrowError> {
+        Self::load(&env, shipment_id)
+    }
+
+    /// Read one funding round of a shipment's escrow, current or archived.
+    pub fn get_escrow_round(
+        env: Env,
+        shipment_id: u64,
+        round: u32,
+    ) -> Result<EscrowRecord, EscrowError> {
+        let current = Self::load(&env, shipment_id)?;
+        if current.round == round {
+            return Ok(current);
+        }
+        env.storage()
+            .persistent()
+            .get(&DataKey::EscrowRound(shipment_id, round))
+            .ok_or(EscrowError::NotFound)
+    }
+
+    /// Every funding round of a shipment's escrow, oldest first.
+    pub fn get_escrow_history(
+        env: Env,
+        shipment_id: u64,
+    ) -> Result<Vec<EscrowRecord>, EscrowError> {
+        let current = Self::load(&env, shipment_id)?;
+        let mut history = Vec::new(&env);
+        for round in 1..current.round {
+            if let Some(record) = env
+                .storage()
+                .persistent()
+                .get(&DataKey::EscrowRound(shipment_id, round))
+            {
+                history.push_back(record);
+            }
+        }
+        history.push_back(current);
+        Ok(history)
+    }
+
+    /// Escrow rounds `party` took part in as `role`, newest first.  Start
+    /// with `cursor = None`; `limit` is capped at `MAX_PAGE_SIZE`, `0` meaning
+    /// the cap.
+    pub fn get_escrows_by_party(
+        env: Env,
+        role: PartyRole,
+        party: Address,
+        cursor: Option<u32>,
+        limit: u32,
+    ) -> EscrowPage {
+        Self::page(&env, IndexKey::Party(role, party), cursor, limit)
+    }
+
+    /// Escrows currently in `status`, most recent transition first.
+    /// Paginated like `get_escrows_by_party`.
+    pub fn get_escrows_by_status(
+        env: Env,
+        status: StatusKind,
+        cursor: Option<u32>,
+        limit: u32,
+    ) -> EscrowPage {
+        Self::page(&env, IndexKey::Status(status), cursor, limit)
+    }
+
+    /// Number of escrows currently in `status`.
+    pub fn count_by_status(env: Env, status: StatusKind) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::StatusCount(status))
+            .unwrap_or(0)
+    }
+
+    /// Tokens held for `party` as `role` across its `Funded`, `Disputed` and
+    /// `Scheduled` escrows, net of milestone tranches already paid.  Pending
+    /// deposits are withdrawable and not counted.
+    pub fn get_locked(env: Env, role: PartyRole, party: Address, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Locked(role, party, token))
+            .unwrap_or(0)
+    }
+
+    /// Read the configured admin address.
+    ///
+    /// Lets external callers (e.g. the backend's Soroban integration layer)
+    /// verify their loaded signing keypair actually matches this contract's
+    /// admin at startup, instead of discovering a mismatch on first real
+    /// admin-gated call.
+    pub fn get_admin(env: Env) -> Result<Address, EscrowError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)
+    }
+
+    pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
+        ADMIN_TRANSFER.pending(&env)
+    }
+
+    pub fn get_signers(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Signers)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Number of approvals required; `0` when no signer set is configured.
+    pub fn get_threshold(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Threshold)
+            .unwrap_or(0)
+    }
+
+    pub fn get_proposal(env: Env, proposal_id: u64) -> Result<Proposal, EscrowError> {
+        Self::load_proposal(&env, proposal_id)
+    }
+
+    /// The challenge window in force, including a shorter one whose wait
+    /// is over.
+    pub fn get_settlement_delay(env: Env) -> u64 {
+        if let Some(pending) = Self::get_pending_delay(env.clone()) {
+            if env.ledger().timestamp() >= pending.effective_at {
+                return pending.delay_seconds;
+            }
+        }
+        env.storage()
+            .instance()
+            .get(&DataKey::SettlementDelay)
+            .unwrap_or(0)
+    }
+
+    pub fn get_pending_delay(env: Env) -> Option<PendingDelay> {
+        env.storage().instance().get(&DataKey::PendingDelay)
+    }
+
+    pub fn get_bond(env: Env, carrier: Address, token: Address) -> BondAccount {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Bond(carrier, token))
+            .unwrap_or_default()
+    }
+
+    pub fn get_bond_cooldown(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::BondCooldown)
+            .unwrap_or(DEFAULT_BOND_COOLDOWN)
+    }
+
+    pub fn get_shipment_contract(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::ShipmentContract)
+    }
+
+    pub fn get_confirmation_window(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::ConfirmationWindow)
+            .unwrap_or(0)
+    }
+
+    pub fn get_deadline_policy(env: Env) -> DeadlinePolicy {
+        env.storage()
+            .instance()
+            .get(&DataKey::DeadlinePolicy)
+            .unwrap_or_default()
+    }
+
+    pub fn get_pauser(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Pauser)
+    }
+
+    pub fn get_pause_state(env: Env) -> PauseState {
+        env.storage()
+            .instance()
+            .get(&DataKey::PauseState)
+            .unwrap_or_default()
+    }
+
+    pub fn get_allowed_tokens(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::AllowedTokens)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn get_cancellation_fees(env: Env) -> CancellationFees {
+        env.storage()
+            .instance()
+            .get(&DataKey::CancellationFees)
+            .unwrap_or_default()
+    }
+
+    pub fn get_commission(env: Env) -> Option<CommissionConfig> {
+        env.storage().instance().get(&DataKey::Commission)
+    }
+
+    pub fn get_arbitration_treasury(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::ArbitrationTreasury)
+    }
+
+    pub fn get_shipper_commission(env: Env, shipper: Address) -> Option<u32> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ShipperCommission(shipper))
+    }
+
+    /// Total commission sent to the treasury in `token`.
+    pub fn get_accrued_fees(env: Env, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AccruedFees(token))
+            .unwrap_or(0)
+    }
+
+    /// Amount of `token` held by the contract across all escrows.
+    pub fn get_balance(env: Env, token: Address) -> i128 {
+        token::Client::new(&env, &token).balance(&env.current_contract_address())
+    }
+
+    /// Amount of `token` the contract owes open escrows, undrawn deposits
+    /// included.
+    pub fn get_liabilities(env: Env, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Liabilities(token))
+            .unwrap_or(0)
+    }
+
+    /// Compare the contract's `token` balance with its liabilities.
+    pub fn reconcile(env: Env, token: Address) -> Reconciliation {
+        let balance = Self::get_balance(env.clone(), token.clone());
+        let liabilities = Self::get_liabilities(env, token);
+        Reconciliation {
+            balance,
+            liabilities,
+            surplus: balance - liabilities,
+        }
+    }
+
+    // ── Helpers ───────────────────────────────────────────────────────────
+
+    /// Admin auth for the legacy single-key settlement entry points.
+    fn require_single_admin(env: &Env) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+
+        if env.storage().instance().has(&DataKey::Signers) {
+            return Err(EscrowError::MultisigRequired);
+        }
+        Ok(())
+    }
+
+    /// `All` is only in effect once both funding and settlement are paused.
+    fn require_not_paused(env: &Env, scope: PauseScope) -> Result<(), EscrowError> {
+        let state = Self::get_pause_state(env.clone());
+        let paused = match scope {
+            PauseScope::Funding => state.funding,
+            PauseScope::Settlement => state.settlement,
+            PauseScope::All => state.funding && state.settlement,
+        };
+        if paused {
+            return Err(EscrowError::Paused);
+        }
+        Ok(())
+    }
+
+    fn do_set_signers(env: &Env, signers: Vec<Address>, threshold: u32) -> Result<(), EscrowError> {
+        if threshold == 0 || threshold > signers.len() {
+            return Err(EscrowError::InvalidThreshold);
+        }
+        for (i, signer) in signers.iter().enumerate() {
+            if signers.first_index_of(&signer) != Some(i as u32) {
+                return Err(EscrowError::InvalidThreshold);
+            }
+        }
+
+        env.storage().instance().set(&DataKey::Signers, &signers);
+        env.storage()
+            .instance()
+            .set(&DataKey::Threshold, &threshold);
+
+        emit(
+            env,
+            symbol_short!("signers"),
+            SignersEvent { signers, threshold },
+        );
+        Ok(())
+    }
+
+    fn do_sweep(env: &Env, token: Address, to: Address, amount: i128) -> Result<(), EscrowError> {
+        // Liabilities only cover records that have been migrated.
+        if Self::schema_version(env.clone()) < SCHEMA_VERSION {
+            return Err(EscrowError::MigrationPending);
+        }
+        if amount <= 0 {
+            return Err(EscrowError::InvalidAmount);
+        }
+        if amount > Self::reconcile(env.clone(), token.clone()).surplus {
+            return Err(EscrowError::InsufficientBalance);
+        }
+
+        token::Client::new(env, &token).transfer(&env.current_contract_address(), &to, &amount);
+
+        emit(
+            env,
+            symbol_short!("swept"),
+            SweepEvent { token, to, amount },
+        );
+        Ok(())
+    }
+
+    fn do_set_shipment_contract(env: &Env, contract: Address) {
+        env.storage()
+            .instance()
+            .set(&DataKey::ShipmentContract, &contract);
+
+        emit(
+            env,
+            symbol_short!("shipments"),
+            ShipmentContractEvent { contract },
+        );
+    }
+
+    fn do_upgrade(env: &Env, wasm_hash: BytesN<32>) {
+        emit(
+            env,
+            symbol_short!("upgraded"),
+            UpgradedEvent {
+                wasm_hash: wasm_hash.clone(),
+                from_version: CONTRACT_VERSION,
+            },
+        );
+        env.deployer().update_current_contract_wasm(wasm_hash);
+    }
+
+    fn do_migrate(env: &Env, shipment_ids: Vec<u64>, complete: bool) -> u32 {
+        // Only while the marker lags: once bumped, a `disallow_token` of the
+        // legacy token must stick.
+        let pending = Self::schema_version(env.clone()) < SCHEMA_VERSION;
+        if let Some(token) = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::TokenContract)
+            .filter(|_| pending)
+        {
+            let mut tokens = Self::get_allowed_tokens(env.clone());
+            if !tokens.contains(&token) {
+                tokens.push_back(token);
+                env.storage()
+                    .instance()
+                    .set(&DataKey::AllowedTokens, &tokens);
+            }
+        }
+
+        let mut migrated = 0;
+        for shipment_id in shipment_ids.iter() {
+            if let Ok(record) = Self::load(env, shipment_id) {
+                Self::store(env, &record);
+                migrated += 1;
+            }
+        }
+
+        if complete {
+            env.storage()
+                .instance()
+                .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+            emit(
+                env,
+                symbol_short!("migrated"),
+                MigratedEvent {
+                    schema_version: SCHEMA_VERSION,
+                },
+            );
+        }
+        migrated
+    }
+
+    fn do_set_commission(
+        env: &Env,
+        bps: u32,
+        treasury: Address,
+        charge_on_refund: bool,
+    ) -> Result<(), EscrowError> {
+        if bps > MAX_COMMISSION_BPS {
+            return Err(EscrowError::InvalidCommission);
+        }
+
+        let config = CommissionConfig {
+            bps,
+            treasury,
+            charge_on_refund,
+        };
+        env.storage().instance().set(&DataKey::Commission, &config);
+
+        emit(env, symbol_short!("commissn"), CommissionEvent { config });
+        Ok(())
+    }
+
+    fn do_set_shipper_commission(
+        env: &Env,
+        shipper: Address,
+        bps: Option<u32>,
+    ) -> Result<(), EscrowError> {
+        let key = DataKey::ShipperCommission(shipper.clone());
+        match bps {
+            Some(rate) if rate > MAX_COMMISSION_BPS => return Err(EscrowError::InvalidCommission),
+            Some(rate) => {
+                env.storage().persistent().set(&key, &rate);
+                env.storage()
+                    .persistent()
+                    .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+            }
+            None => env.storage().persistent().remove(&key),
+        }
+
+        emit(
+            env,
+            symbol_short!("override"),
+            OverrideEvent { shipper, bps },
+        );
+        Ok(())
+    }
+
+    fn do_set_arbitration_treasury(env: &Env, treasury: Address) {
+        env.storage()
+            .instance()
+            .set(&DataKey::ArbitrationTreasury, &treasury);
+
+        emit(env, symbol_short!("treasury"), TreasuryEvent { treasury });
+    }
+
+    fn require_signer(env: &Env, signer: &Address) -> Result<(), EscrowError> {
+        let signers: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&DataKey::Signers)
+            .ok_or(EscrowError::NotSigner)?;
+        if !signers.contains(signer) {
+            return Err(EscrowError::NotSigner);
+        }
+        Ok(())
+    }
+
+    fn do_release(env: &Env, shipment_id: u64) -> Result<(), EscrowError> {
+        let record = Self::load(env, shipment_id)?;
+
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+        if !record.legs.is_empty() {
+            return Err(EscrowError::PaidPerLeg);
+        }
+
+        Self::schedule_or_settle(env, record, SettlementKind::Release);
+        Ok(())
+    }
+
+    fn do_release_milestone(env: &Env, shipment_id: u64, index: u32) -> Result<(), EscrowError> {
+        let record = Self::load(env, shipment_id)?;
+
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+        let milestone = record
+            .milestones
+            .get(index)
+            .ok_or(EscrowError::MilestoneNotFound)?;
+        if milestone.released {
+            return Err(EscrowError::MilestoneAlreadyReleased);
+        }
+
+        Self::schedule_or_settle(env, record, SettlementKind::Milestone(index));
+        Ok(())
+    }
+
+    /// `action` must validate before it writes anything, so a failed shipment
+    /// leaves no trace.
+    fn settle_batch(
+        env: &Env,
+        shipment_ids: Vec<u64>,
+        action: fn(&Env, u64) -> Result<(), EscrowError>,
+    ) -> Result<Vec<BatchResult>, EscrowError> {
+        Self::require_not_paused(env, PauseScope::Settlement)?;
+        Self::require_single_admin(env)?;
+        if shipment_ids.is_empty() || shipment_ids.len() > MAX_BATCH_SIZE {
+            return Err(EscrowError::InvalidBatch);
+        }
+
+        let mut results = Vec::new(env);
+        for shipment_id in shipment_ids.iter() {
+            let outcome = match action(env, shipment_id) {
+                Err(e) => BatchOutcome::Failed(e as u32),
+                Ok(()) => match Self::load(env, shipment_id)?.status {
+                    EscrowStatus::Scheduled(_) => BatchOutcome::Scheduled,
+                    _ => BatchOutcome::Settled,
+                },
+            };
+            results.push_back(BatchResult {
+                shipment_id,
+                outcome,
+            });
+        }
+        Ok(results)
+    }
+
+    fn do_reopen(env: &Env, shipment_id: u64) -> Result<(), EscrowError> {
+        let record = Self::load(env, shipment_id)?;
+        let round = record.round;
+
+        if record.status == EscrowStatus::Pending {
+            Self::abandon(env, record);
+        } else {
+            if !Self::is_settled(&record.status) {
+                return Err(EscrowError::InvalidStatus);
+            }
+            // Overpaid deposits would be archived with the round; withdraw first.
+            if record.deposited > record.amount {
+                return Err(EscrowError::InvalidStatus);
+            }
+
+            env.storage()
+                .persistent()
+                .set(&DataKey::Reopened(shipment_id), &true);
+            env.storage().persistent().extend_ttl(
+                &DataKey::Reopened(shipment_id),
+                TTL_LEDGERS,
+                TTL_LEDGERS,
+            );
+        }
+
+        emit(
+            env,
+            symbol_short!("reopened"),
+            ReopenedEvent { shipment_id, round },
+        );
+        Ok(())
+    }
+
+    /// When a streaming escrow started vesting: the pinned start, else the
+    /// linked shipment's pickup.  `None` for a lump-sum escrow or before
+    /// pickup.
+    fn stream_start(env: &Env, record: &EscrowRecord) -> Option<u64> {
+        if record.stream.duration == 0 || record.stream.started_at.is_some() {
+            return record.stream.started_at;
+        }
+        let contract: Address = env.storage().instance().get(&DataKey::ShipmentContract)?;
+        let shipment = ShipmentContractClient::new(env, &contract)
+            .try_get_shipment(&record.shipment_id)
+            .ok()?
+            .ok()?;
+        if shipment.shipper != record.shipper
+            || shipment.carrier != Some(record.carrier.clone())
+            || shipment.picked_up_at == 0
+        {
+            return None;
+        }
+        Some(shipment.picked_up_at)
+    }
+
+    /// Total a streaming escrow has vested, frozen at the dispute if any.
+    fn vested(env: &Env, record: &EscrowRecord) -> i128 {
+        let stream = &record.stream;
+        let started_at = match Self::stream_start(env, record) {
+            Some(started_at) => started_at,
+            None => return 0,
+        };
+        let now = stream.frozen_at.unwrap_or_else(|| env.ledger().timestamp());
+        let elapsed = now.saturating_sub(started_at).min(stream.duration);
+        record.amount * elapsed as i128 / stream.duration as i128
+    }
+
+    /// Pay the payee what a streaming escrow has vested beyond earlier draws,
+    /// net of commission, and return the net amount.  The caller stores the
+    /// record.
+    fn pay_vested(env: &Env, token: &token::Client, record: &mut EscrowRecord) -> i128 {
+        if record.stream.started_at.is_none() {
+            record.stream.started_at = Self::stream_start(env, record);
+        }
+        let vested = Self::vested(env, record);
+        let gross = vested - record.released_amount;
+        if gross <= 0 {
+            return 0;
+        }
+
+        let payee = Self::payee(record);
+        let fee = Self::take_commission(env, token, record, gross, false);
+        if gross - fee > 0 {
+            token.transfer(&env.current_contract_address(), &payee, &(gross - fee));
+        }
+        record.released_amount = vested;
+
+        emit(
+            env,
+            symbol_short!("streamed"),
+            StreamEvent {
+                shipment_id: record.shipment_id,
+                recipient: payee,
+                amount: gross - fee,
+                fee,
+                vested,
+            },
+        );
+        gross - fee
+    }
+
+    /// Receives the escrow's carrier payouts.
+    fn payee(record: &EscrowRecord) -> Address {
+        record
+            .assignees
+            .last()
+            .unwrap_or_else(|| record.carrier.clone())
+    }
+
+    fn require_assignable(record: &EscrowRecord) -> Result<(), EscrowError> {
+        match record.status {
+            EscrowStatus::Pending | EscrowStatus::Funded | EscrowStatus::Disputed => Ok(()),
+            _ => Err(EscrowError::InvalidStatus),
+        }
+    }
+
+    fn is_settled(status: &EscrowStatus) -> bool {
+        matches!(
+            status,
+            EscrowStatus::Released | EscrowStatus::Refunded | EscrowStatus::Split
+        )
+    }
+
+    fn do_refund(env: &Env, shipment_id: u64) -> Result<(), EscrowError> {
+        let record = Self::load(env, shipment_id)?;
+
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let kind = if Self::get_cancellation_fees(env.clone()) == CancellationFees::default() {
+            SettlementKind::Refund
+        } else {
+            SettlementKind::Cancel(Self::cancel_stage(env, &record)?)
+        };
+        Self::schedule_or_settle(env, record, kind);
+        Ok(())
+    }
+
+    fn do_cancel(env: &Env, shipment_id: u64) -> Result<(), EscrowError> {
+        let record = Self::load(env, shipment_id)?;
+
+        if record.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let stage = Self::cancel_stage(env, &record)?;
+        Self::schedule_or_settle(env, record, SettlementKind::Cancel(stage));
+        Ok(())
+    }
+
+    /// How far the linked shipment got, which prices the cancellation.  A
+    /// cancelled shipment was at most accepted: the shipment contract only
+    /// cancels from `Created` or `Accepted`.
+    fn cancel_stage(env: &Env, record: &EscrowRecord) -> Result<CancelStage, EscrowError> {
+        let contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::ShipmentContract)
+            .ok_or(EscrowError::ShipmentContractNotSet)?;
+        let shipment = match ShipmentContractClient::new(env, &contract)
+            .try_get_shipment(&record.shipment_id)
+        {
+            Ok(Ok(shipment)) => shipment,
+            _ => return Err(EscrowError::ShipmentNotSettled),
+        };
+        if shipment.shipper != record.shipper {
+            return Err(EscrowError::PartyMismatch);
+        }
+
+        match (shipment.status, shipment.carrier) {
+            (ShipmentStatus::Created, _) | (ShipmentStatus::Cancelled, None) => {
+                Ok(CancelStage::Pending)
+            }
+            (ShipmentStatus::Accepted, _) | (ShipmentStatus::Cancelled, Some(_)) => {
+                Ok(CancelStage::Accepted)
+            }
+            (ShipmentStatus::InTransit, _)
+            | (ShipmentStatus::Delivered, _)
+            | (ShipmentStatus::Disputed, _) => Ok(CancelStage::InTransit),
+            (ShipmentStatus::Completed, _) | (ShipmentStatus::PartiallyCompleted, _) => {
+                Err(EscrowError::InvalidStatus)
+            }
+        }
+    }
+
+    fn all_or_nothing(release_to_carrier: bool) -> DisputeRuling {
+        DisputeRuling {
+            carrier_bps: if release_to_carrier {
+                BPS_DENOMINATOR
+            } else {
+                0
+            },
+            fee_bps: 0,
+            fee_recipient: None,
+            slash_bps: 0,
+        }
+    }
+
+    fn do_resolve(env: &Env, shipment_id: u64, ruling: DisputeRuling) -> Result<(), EscrowError> {
+        let record = Self::load(env, shipment_id)?;
+
+        if record.status != EscrowStatus::Disputed {
+            return Err(EscrowError::InvalidStatus);
+        }
+        Self::apply_ruling(env, record, ruling)
+    }
+
+    /// Split what the escrow holds per `ruling` and close it.
+    fn apply_ruling(
+        env: &Env,
+        mut record: EscrowRecord,
+        ruling: DisputeRuling,
+    ) -> Result<(), EscrowError> {
+        if ruling.carrier_bps > BPS_DENOMINATOR || ruling.fee_bps > MAX_ARBITRATION_FEE_BPS {
+            return Err(EscrowError::InvalidRuling);
+        }
+        let fee_recipient = match ruling.fee_bps {
+            0 => None,
+            _ => {
+                let treasury = Self::get_arbitration_treasury(env.clone());
+                if treasury.is_none() || ruling.fee_recipient != treasury {
+                    return Err(EscrowError::InvalidRuling);
+                }
+                treasury
+            }
+        };
+        if ruling.slash_bps > BPS_DENOMINATOR
+            || (ruling.slash_bps > 0 && ruling.carrier_bps == BPS_DENOMINATOR)
+        {
+            return Err(EscrowError::InvalidRuling);
+        }
+        if ruling.slash_bps > 0 && record.bond == 0 {
+            return Err(EscrowError::NoBondPosted);
+        }
+
+        // Tranches already paid out stay with the carrier, and so does what a
+        // stream vested before the dispute froze it; the ruling splits the rest.
+        let token = token::Client::new(env, &record.token);
+        Self::pay_vested(env, &token, &mut record);
+        let held = record.amount - record.released_amount;
+        let fee = held * ruling.fee_bps as i128 / BPS_DENOMINATOR as i128;
+        let carrier_amount = (held - fee) * ruling.carrier_bps as i128 / BPS_DENOMINATOR as i128;
+        let amounts = SplitAmounts {
+            carrier: carrier_amount,
+            shipper: held - fee - carrier_amount,
+            fee,
+        };
+
+        let this = env.current_contract_address();
+        Self::pay_carriers(env, &token, &record, amounts.carrier);
+        Self::refund_depositors(env, &token, &record, amounts.shipper);
+        if let Some(recipient) = fee_recipient {
+            if amounts.fee > 0 {
+                token.transfer(&this, &recipient, &amounts.fee);
+            }
+        }
+
+        record.status = match ruling.carrier_bps {
+            BPS_DENOMINATOR => EscrowStatus::Released,
+            0 => EscrowStatus::Refunded,
+            _ => EscrowStatus::Split,
+        };
+        record.released_amount += amounts.carrier;
+        record.resolution = Resolution::Ruled(ruling.clone(), amounts.clone());
+        record.settled_at = env.ledger().timestamp();
+        if ruling.slash_bps > 0 {
+            Self::slash_bond(env, &token, &mut record, ruling.slash_bps);
+        }
+        Self::free_bond(env, &record);
+        Self::store(env, &record);
+
+        emit(
+            env,
+            symbol_short!("resolved"),
+            ResolvedEvent {
+                shipment_id: record.shipment_id,
+                ruling,
+                amounts,
+            },
+        );
+        Ok(())
+    }
+
+    /// Settle now when no delay is configured, otherwise open the challenge
+    /// window.
+    fn schedule_or_settle(env: &Env, mut record: EscrowRecord, kind: SettlementKind) {
+        let delay = Self::get_settlement_delay(env.clone());
+        if delay == 0 {
+            Self::settle(env, record, kind);
+            return;
+        }
+
+        record.status = EscrowStatus::Scheduled(kind.clone());
+        record.execute_after = env.ledger().timestamp() + delay;
+        record.commission = match Self::commission_terms(env, &record.shipper) {
+            Some(config) => CommissionTerms::Charged(config),
+            None => CommissionTerms::Uncharged,
+        };
+        Self::store(env, &record);
+
+        emit(
+            env,
+            symbol_short!("scheduled"),
+            ScheduledEvent {
+                shipment_id: record.shipment_id,
+                kind,
+                execute_after: record.execute_after,
+            },
+        );
+    }
+
+    fn settle(env: &Env, mut record: EscrowRecord, kind: SettlementKind) {
+        let token = token::Client::new(env, &record.token);
+
+        let (recipient, status, name) = match kind {
+            SettlementKind::Milestone(index) => {
+                Self::settle_milestone(env, &token, record, index);
+                return;
+            }
+            SettlementKind::Cancel(stage) => {
+                Self::settle_cancellation(env, &token, record, stage);
+                return;
+            }
+            SettlementKind::Release => (
+                Self::payee(&record),
+                EscrowStatus::Released,
+                symbol_short!("released"),
+            ),
+            SettlementKind::Refund => (
+                record.payer.clone(),
+                EscrowStatus::Refunded,
+                symbol_short!("refunded"),
+            ),
+        };
+
+        // Tranches already paid out stay with the carrier, and so does
+        // whatever a stream has vested.
+        if status == EscrowStatus::Refunded {
+            Self::pay_vested(env, &token, &mut record);
+        }
+        let mut remaining = record.amount - record.released_amount;
+        if status == EscrowStatus::Released {
+            let penalty = Self::apply_late_penalty(env, &token, &mut record, remaining);
+            remaining -= penalty;
+        }
+        let fee = Self::take_commission(
+            env,
+            &token,
+            &mut record,
+            remaining,
+            status == EscrowStatus::Refunded,
+        );
+        if status == EscrowStatus::Refunded {
+            Self::refund_depositors(env, &token, &record, remaining - fee);
+        } else {
+            Self::pay_carriers(env, &token, &record, remaining - fee);
+        }
+
+        if status == EscrowStatus::Released {
+            record.released_amount = record.amount;
+            for i in 0..record.milestones.len() {
+                let mut milestone = record.milestones.get(i).unwrap();
+                milestone.released = true;
+                record.milestones.set(i, milestone);
+            }
+        }
+        record.status = status;
+        record.execute_after = 0;
+        record.commission = CommissionTerms::Uncharged;
+        record.settled_at = env.ledger().timestamp();
+        Self::free_bond(env, &record);
+        Self::store(env, &record);
+
+        emit(
+            env,
+            name,
+            SettledEvent {
+                shipment_id: record.shipment_id,
+                recipient,
+                amount: remaining - fee,
+                fee,
+            },
+        );
+    }
+
+    /// Pay one tranche.  The last outstanding tranche takes whatever is left
+    /// so rounding dust never stays locked, and bears any late-delivery
+    /// penalty.
+    fn settle_milestone(env: &Env, token: &token::Client, mut record: EscrowRecord, index: u32) {
+        let mut milestone = record.milestones.get(index).unwrap();
+        let outstanding = record.milestones.iter().filter(|m| !m.released).count();
+
+        let tranche = if outstanding == 1 {
+            record.amount - record.released_amount
+        } else {
+            record.amount * milestone.bps as i128 / BPS_DENOMINATOR as i128
+        };
+        let penalty = if outstanding == 1 {
+            Self::apply_late_penalty(env, token, &mut record, tranche)
+        } else {
+            0
+        };
+        let fee = Self::take_commission(env, token, &mut record, tranche - penalty, false);
+        let payout = tranche - penalty - fee;
+        if payout > 0 {
+            token.transfer(
+                &env.current_contract_address(),
+                &Self::payee(&record),
+                &payout,
+            );
+        }
+
+        milestone.released = true;
+        record.milestones.set(index, milestone.clone());
+        record.released_amount += tranche;
+        record.execute_after = 0;
+        record.commission = CommissionTerms::Uncharged;
+        if outstanding == 1 {
+            record.status = EscrowStatus::Released;
+            record.settled_at = env.ledger().timestamp();
+            Self::free_bond(env, &record);
+        } else {
+            record.status = EscrowStatus::Funded;
+        }
+        Self::store(env, &record);
+
+        emit(
+            env,
+            symbol_short!("milestone"),
+            MilestoneEvent {
+                shipment_id: record.shipment_id,
+                index,
+                label: milestone.label,
+                amount: payout,
+                fee,
+                remaining: record.amount - record.released_amount,
+            },
+        );
+    }
+
+    /// Pay one leg's price to `carrier`.  Legs carried by the escrow's own
+    /// carrier follow its payout assignment.  The last unpaid leg bears any
+    /// late-delivery penalty.
+    fn settle_leg(env: &Env, mut record: EscrowRecord, index: u32, carrier: Address) {
+        let token = token::Client::new(env, &record.token);
+        let mut payout = record.legs.get(index).unwrap();
+        let outstanding = record.legs.iter().filter(|l| l.paid_to.is_none()).count();
+
+        let recipient = if carrier == record.carrier {
+            Self::payee(&record)
+        } else {
+            carrier
+        };
+        let penalty = if outstanding == 1 {
+            Self::apply_late_penalty(env, &token, &mut record, payout.price)
+        } else {
+            0
+        };
+        let fee = Self::take_commission(env, &token, &mut record, payout.price - penalty, false);
+        let amount = payout.price - penalty - fee;
+        if amount > 0 {
+            token.transfer(&env.current_contract_address(), &recipient, &amount);
+        }
+
+        payout.paid_to = Some(recipient.clone());
+        record.legs.set(index, payout.clone());
+        record.released_amount += payout.price;
+        if outstanding == 1 {
+            record.status = EscrowStatus::Released;
+            record.settled_at = env.ledger().timestamp();
+            Self::free_bond(env, &record);
+        }
+        Self::store(env, &record);
+
+        emit(
+            env,
+            symbol_short!("leg_paid"),
+            LegPaidEvent {
+                shipment_id: record.shipment_id,
+                index,
+                recipient,
+                amount,
+                fee,
+                remaining: record.amount - record.released_amount,
+            },
+        );
+    }
+
+    /// Whether every unpaid leg of a multi-leg escrow has been confirmed on
+    /// the linked shipment.
+    fn legs_confirmed(client: &ShipmentContractClient, record: &EscrowRecord) -> bool {
+        let legs = match client.try_get_legs(&record.shipment_id) {
+            Ok(Ok(legs)) => legs,
+            _ => return false,
+        };
+        (0..record.legs.len()).all(|i| {
+            record.legs.get(i).unwrap().paid_to.is_some()
+                || legs.get(i).map(|l| l.status) == Some(ShipmentStatus::Completed)
+        })
+    }
+
+    /// Pay the carrier side of a whole-escrow settlement.  A multi-leg escrow
+    /// splits it over the unpaid legs by price, each to its own carrier; the
+    /// last unpaid leg takes the rounding dust.
+    fn pay_carriers(env: &Env, token: &token::Client, record: &EscrowRecord, amount: i128) {
+        if amount <= 0 {
+            return;
+        }
+        let this = env.current_contract_address();
+        let unpaid = || record.legs.iter().filter(|l| l.paid_to.is_none());
+        let total: i128 = unpaid().map(|l| l.price).sum();
+        if total == 0 {
+            token.transfer(&this, &Self::payee(record), &amount);
+            return;
+        }
+
+        let count = unpaid().count();
+        let mut rest = amount;
+        for (i, leg) in unpaid().enumerate() {
+            let share = if i + 1 == count {
+                rest
+            } else {
+                amount * leg.price / total
+            };
+            rest -= share;
+            let recipient = if leg.carrier == record.carrier {
+                Self::payee(record)
+            } else {
+                leg.carrier
+            };
+            if share > 0 {
+                token.transfer(&this, &recipient, &share);
+            }
+        }
+    }
+
+    /// Send the platform commission on a `gross` payout to the treasury and
+    /// return it.  The caller pays out `gross - fee`.
+    fn take_commission(
+        env: &Env,
+        token: &token::Client,
+        record: &mut EscrowRecord,
+        gross: i128,
+        is_refund: bool,
+    ) -> i128 {
+        // A scheduled settlement is charged on the terms it was scheduled under.
+        let terms = if record.execute_after != 0 {
+            match &record.commission {
+                CommissionTerms::Charged(config) => Some(config.clone()),
+                CommissionTerms::Uncharged => None,
+            }
+        } else {
+            Self::commission_terms(env, &record.shipper)
+        };
+        let config = match terms {
+            Some(config) => config,
+            None => return 0,
+        };
+        if is_refund && !config.charge_on_refund {
+            return 0;
+        }
+
+        let fee = gross * config.bps as i128 / BPS_DENOMINATOR as i128;
+        if fee <= 0 {
+            return 0;
+        }
+        token.transfer(&env.current_contract_address(), &config.treasury, &fee);
+
+        let key = DataKey::AccruedFees(record.token.clone());
+        let accrued: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(accrued + fee));
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+
+        record.fee_charged += fee;
+        fee
+    }
+
+    /// The commission currently charged on `shipper`'s payouts, with their
+    /// override applied.
+    fn commission_terms(env: &Env, shipper: &Address) -> Option<CommissionConfig> {
+        let mut config: CommissionConfig = env.storage().instance().get(&DataKey::Commission)?;
+        if let Some(bps) = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ShipperCommission(shipper.clone()))
+        {
+            config.bps = bps;
+        }
+        Some(config)
+    }
+
+    /// Refund the linked shipment's late-delivery penalty on a `payout` to
+    /// the payer and return it.  No shipment link, no promise or an on-time
+    /// delivery mean no penalty.
+    fn apply_late_penalty(
+        env: &Env,
+        token: &token::Client,
+        record: &mut EscrowRecord,
+        payout: i128,
+    ) -> i128 {
+        let contract: Address = match env.storage().instance().get(&DataKey::ShipmentContract) {
+            Some(contract) => contract,
+            None => return 0,
+        };
+        let shipment = match ShipmentContractClient::new(env, &contract)
+            .try_get_shipment(&record.shipment_id)
+        {
+            Ok(Ok(shipment)) => shipment,
+            _ => return 0,
+        };
+        if shipment.shipper != record.shipper
+            || shipment.deliver_by == 0
+            || shipment.delivered_at <= shipment.deliver_by
+        {
+            return 0;
+        }
+
+        let hours_late = (shipment.delivered_at - shipment.deliver_by) / 3_600;
+        let bps = (hours_late.saturating_mul(shipment.penalty.bps_per_hour as u64))
+            .min(shipment.penalty.max_bps as u64)
+            .min(BPS_DENOMINATOR as u64);
+        let amount = payout * bps as i128 / BPS_DENOMINATOR as i128;
+        if amount <= 0 {
+            return 0;
+        }
+        Self::refund_depositors(env, token, record, amount);
+        record.penalty = amount;
+
+        emit(
+            env,
+            symbol_short!("penalty"),
+            PenaltyEvent {
+                shipment_id: record.shipment_id,
+                hours_late,
+                amount,
+            },
+        );
+        amount
+    }
+
+    /// Pay `slash_bps` of the carrier's bond on `record` to the shipper.
+    fn slash_bond(env: &Env, token: &token::Client, record: &mut EscrowRecord, slash_bps: u32) {
+        let amount = record.bond * slash_bps as i128 / BPS_DENOMINATOR as i128;
+        if amount <= 0 {
+            return;
+        }
+        let mut account = Self::get_bond(env.clone(), record.carrier.clone(), record.token.clone());
+        account.balance -= amount;
+        account.committed -= amount;
+        Self::store_bond(env, &record.carrier, &record.token, &account);
+        Self::add_total(env, &DataKey::Liabilities(record.token.clone()), -amount);
+        token.transfer(&env.current_contract_address(), &record.shipper, &amount);
+        record.bond_slashed = amount;
+
+        emit(
+            env,
+            symbol_short!("slashed"),
+            BondPostEvent {
+                shipment_id: record.shipment_id,
+                carrier: record.carrier.clone(),
+                amount,
+            },
+        );
+    }
+
+    /// Return what is left of the bond on a settling escrow to the carrier's
+    /// free balance.
+    fn free_bond(env: &Env, record: &EscrowRecord) {
+        let amount = record.bond - record.bond_slashed;
+        if amount <= 0 {
+            return;
+        }
+        let mut account = Self::get_bond(env.clone(), record.carrier.clone(), record.token.clone());
+        account.committed -= amount;
+        Self::store_bond(env, &record.carrier, &record.token, &account);
+
+        emit(
+            env,
+            symbol_short!("bond_free"),
+            BondPostEvent {
+                shipment_id: record.shipment_id,
+                carrier: record.carrier.clone(),
+                amount,
+            },
+        );
+    }
+
+    fn pull_bond(
+        env: &Env,
+        carrier: &Address,
+        token: &Address,
+        amount: i128,
+        account: &mut BondAccount,
+    ) {
+        token::Client::new(env, token).transfer_from(
+            &env.current_contract_address(),
+            carrier,
+            &env.current_contract_address(),
+            &amount,
+        );
+        account.balance += amount;
+        Self::add_total(env, &DataKey::Liabilities(token.clone()), amount);
+
+        emit(
+            env,
+            symbol_short!("bond_in"),
+            BondEvent {
+                carrier: carrier.clone(),
+                token: token.clone(),
+                amount,
+                balance: account.balance,
+            },
+        );
+    }
+
+    fn store_bond(env: &Env, carrier: &Address, token: &Address, account: &BondAccount) {
+        let key = DataKey::Bond(carrier.clone(), token.clone());
+        env.storage().persistent().set(&key, account);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+    }
+
+    /// Split the held funds per the cancellation fee table at execution time.
+    fn settle_cancellation(
+        env: &Env,
+        token: &token::Client,
+        mut record: EscrowRecord,
+        stage: CancelStage,
+    ) {
+        let held = record.amount - record.released_amount;
+        // A started stream pays what has vested instead of the fee table.
+        let compensation = if Self::stream_start(env, &record).is_some() {
+            (Self::vested(env, &record) - record.released_amount).max(0)
+        } else {
+            let bps = Self::get_cancellation_fees(env.clone()).bps(stage);
+            held * bps as i128 / BPS_DENOMINATOR as i128
+        };
+        let refund = held - compensation;
+
+        let compensation_fee = Self::take_commission(env, token, &mut record, compensation, false);
+        Self::pay_carriers(env, token, &record, compensation - compensation_fee);
+        let refund_fee = Self::take_commission(env, token, &mut record, refund, true);
+        Self::refund_depositors(env, token, &record, refund - refund_fee);
+
+        record.status = EscrowStatus::Refunded;
+        record.released_amount += compensation;
+        record.execute_after = 0;
+        record.commission = CommissionTerms::Uncharged;
+        record.settled_at = env.ledger().timestamp();
+        Self::free_bond(env, &record);
+        Self::store(env, &record);
+
+        emit(
+            env,
+            symbol_short!("cancelled"),
+            CancelledEvent {
+                shipment_id: record.shipment_id,
+                stage,
+                refund: refund - refund_fee,
+                compensation: compensation - compensation_fee,
+                fee: compensation_fee + refund_fee,
+            },
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn open_escrow(
+        env: &Env,
+        payer: Address,
+        shipper: Address,
+        carrier: Address,
+        shipment_id: u64,
+        token_addr: Address,
+        amount: i128,
+        milestones: Vec<Milestone>,
+        prefund: bool,
+        stream_duration: u64,
+        legs: Vec<LegPayout>,
+    ) -> Result<(), EscrowError> {
+        Self::require_not_paused(env, PauseScope::Funding)?;
+        shipper.require_auth();
+        if payer != shipper {
+            payer.require_auth();
+        }
+
+        if amount <= 0 {
+            return Err(EscrowError::InvalidAmount);
+        }
+        if !env.storage().instance().has(&DataKey::Admin) {
+            return Err(EscrowError::NotInitialized);
+        }
+        if !Self::get_allowed_tokens(env.clone()).contains(&token_addr) {
+            return Err(EscrowError::TokenNotAllowed);
+        }
+
+        // Records are append-only: a shipment is funded again only after the
+        // admin reopens its settled escrow, and the settled round is archived.
+        let mut round = 1;
+        if let Ok(existing) = Self::load(env, shipment_id) {
+            if !Self::is_settled(&existing.status) {
+                return Err(EscrowError::AlreadyFunded);
+            }
+            let reopened = DataKey::Reopened(shipment_id);
+            if !env.storage().persistent().has(&reopened) {
+                return Err(EscrowError::SettlementFinal);
+            }
+            env.storage().persistent().remove(&reopened);
+
+            let archived = DataKey::EscrowRound(shipment_id, existing.round);
+            env.storage().persistent().set(&archived, &existing);
+            env.storage()
+                .persistent()
+                .extend_ttl(&archived, TTL_LEDGERS, TTL_LEDGERS);
+            round = existing.round + 1;
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Deposits(shipment_id));
+
+        if prefund {
+            // Pull tokens from payer into this contract.
+            let token = token::Client::new(env, &token_addr);
+            // transfer_from: spender=this_contract, from=payer, to=this_contract, amount
+            token.transfer_from(
+                &env.current_contract_address(),
+                &payer,
+                &env.current_contract_address(),
+                &amount,
+            );
+        }
+
+        let now = env.ledger().timestamp();
+        let policy = Self::get_deadline_policy(env.clone());
+        let deadline = |within: u64| if within == 0 { 0 } else { now + within };
+        let record = EscrowRecord {
+            shipment_id,
+            round,
+            shipper: shipper.clone(),
+            payer: payer.clone(),
+            carrier: carrier.clone(),
+            token: token_addr.clone(),
+            amount,
+            deposited: if prefund { amount } else { 0 },
+            status: if prefund {
+                EscrowStatus::Funded
+            } else {
+                EscrowStatus::Pending
+            },
+            funded_at: if prefund { now } else { 0 },
+            settled_at: 0,
+            execute_after: 0,
+            commission: CommissionTerms::Uncharged,
+            milestones,
+            released_amount: 0,
+            resolution: Resolution::Unresolved,
+            fee_charged: 0,
+            accept_by: deadline(policy.accept_within),
+            pickup_by: deadline(policy.pickup_within),
+            penalty: 0,
+            bond: 0,
+            bond_slashed: 0,
+            assignees: Vec::new(env),
+            stream: Stream {
+                duration: stream_duration,
+                ..Default::default()
+            },
+            legs,
+        };
+        Self::store(env, &record);
+
+        emit(
+            env,
+            if prefund {
+                symbol_short!("funded")
+            } else {
+                symbol_short!("created")
+            },
+            FundedEvent {
+                shipment_id,
+                round,
+                shipper,
+                payer,
+                carrier,
+                token: token_addr,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Move an incrementally funded escrow between `Pending` and `Funded` as
+    /// deposits or the agreed amount change.
+    fn update_funding(env: &Env, record: &mut EscrowRecord) {
+        let covered = record.deposited >= record.amount;
+        match (&record.status, covered) {
+            (EscrowStatus::Pending, true) => {
+                record.status = EscrowStatus::Funded;
+                record.funded_at = env.ledger().timestamp();
+                emit(
+                    env,
+                    symbol_short!("funded"),
+                    FundedEvent {
+                        shipment_id: record.shipment_id,
+                        round: record.round,
+                        shipper: record.shipper.clone(),
+                        payer: record.payer.clone(),
+                        carrier: record.carrier.clone(),
+                        token: record.token.clone(),
+                        amount: record.amount,
+                    },
+                );
+            }
+            (EscrowStatus::Funded, false) => record.status = EscrowStatus::Pending,
+            _ => {}
+        }
+    }
+
+    /// What each depositor has put in this round.  Escrows nobody has
+    /// `deposit`ed into were paid in full by the payer.
+    fn deposits(env: &Env, record: &EscrowRecord) -> Map<Address, i128> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Deposits(record.shipment_id))
+            .unwrap_or_else(|| Map::from_array(env, [(record.payer.clone(), record.deposited)]))
+    }
+
+    fn store_deposits(env: &Env, shipment_id: u64, deposits: &Map<Address, i128>) {
+        let key = DataKey::Deposits(shipment_id);
+        env.storage().persistent().set(&key, deposits);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+    }
+
+    /// Pay `amount` back to the escrow's depositors, pro rata to what each
+    /// put in.  The payer also takes the rounding dust.  Returns each share.
+    fn refund_depositors(
+        env: &Env,
+        token: &token::Client,
+        record: &EscrowRecord,
+        amount: i128,
+    ) -> Map<Address, i128> {
+        let mut shares = Map::new(env);
+        if amount <= 0 {
+            return shares;
+        }
+        let deposits = Self::deposits(env, record);
+        let total: i128 = deposits.values().iter().sum();
+        let mut rest = amount;
+        for (depositor, deposit) in deposits.iter() {
+            if depositor == record.payer || total <= 0 {
+                continue;
+            }
+            let share = amount * deposit / total;
+            if share > 0 {
+                shares.set(depositor, share);
+                rest -= share;
+            }
+        }
+        if rest > 0 {
+            shares.set(record.payer.clone(), rest);
+        }
+
+        let this = env.current_contract_address();
+        for (depositor, share) in shares.iter() {
+            if share > 0 {
+                token.transfer(&this, &depositor, &share);
+            }
+        }
+        shares
+    }
+
+    /// Hand `amount` of the deposits back to the depositors and take it off
+    /// their balances.
+    fn return_deposits(env: &Env, record: &mut EscrowRecord, amount: i128, name: Symbol) {
+        let token = token::Client::new(env, &record.token);
+        let mut deposits = Self::deposits(env, record);
+        for (depositor, share) in Self::refund_depositors(env, &token, record, amount).iter() {
+            let left = deposits.get(depositor.clone()).unwrap_or(0) - share;
+            deposits.set(depositor.clone(), left.max(0));
+            emit(
+                env,
+                name.clone(),
+                SettledEvent {
+                    shipment_id: record.shipment_id,
+                    recipient: depositor,
+                    amount: share,
+                    fee: 0,
+                },
+            );
+        }
+        Self::store_deposits(env, record.shipment_id, &deposits);
+        record.deposited -= amount;
+    }
+
+    /// Close a `Pending` round: return what is on deposit beyond the tranches
+    /// already paid, and let the shipment be funded afresh.  The round is
+    /// settled at what it paid out.
+    fn abandon(env: &Env, mut record: EscrowRecord) {
+        let unpaid = record.deposited - record.released_amount;
+        if unpaid > 0 {
+            Self::return_deposits(env, &mut record, unpaid, symbol_short!("abandoned"));
+        }
+        record.amount = record.released_amount;
+        record.status = EscrowStatus::Refunded;
+        record.settled_at = env.ledger().timestamp();
+        Self::free_bond(env, &record);
+        Self::store(env, &record);
+
+        let reopened = DataKey::Reopened(record.shipment_id);
+        env.storage().persistent().set(&reopened, &true);
+        env.storage()
+            .persistent()
+            .extend_ttl(&reopened, TTL_LEDGERS, TTL_LEDGERS);
+    }
+
+    fn load(env: &Env, shipment_id: u64) -> Result<EscrowRecord, EscrowError> {
+        let key = DataKey::Escrow(shipment_id);
+        let fields: Map<Symbol, Val> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(EscrowError::NotFound)?;
+        if fields.contains_key(symbol_short!("token")) {
+            return Ok(env.storage().persistent().get(&key).unwrap());
+        }
+
+        // Schema 1 record: upgrade on read; `migrate` persists it.
+        let old: EscrowRecordV1 = env.storage().persistent().get(&key).unwrap();
+        let token = env
+            .storage()
+            .instance()
+            .get(&DataKey::TokenContract)
+            .ok_or(EscrowError::MigrationPending)?;
+        Ok(EscrowRecord {
+            shipment_id: old.shipment_id,
+            round: 1,
+            shipper: old.shipper.clone(),
+            payer: old.shipper,
+            carrier: old.carrier,
+            token,
+            amount: old.amount,
+            deposited: old.amount,
+            status: old.status,
+            funded_at: old.funded_at,
+            settled_at: old.settled_at,
+            execute_after: 0,
+            commission: CommissionTerms::Uncharged,
+            milestones: Vec::new(env),
+            released_amount: 0,
+            resolution: Resolution::Unresolved,
+            fee_charged: 0,
+            accept_by: 0,
+            pickup_by: 0,
+            penalty: 0,
+            bond: 0,
+            bond_slashed: 0,
+            assignees: Vec::new(env),
+            stream: Stream::default(),
+            legs: Vec::new(env),
+        })
+    }
+
+    fn store(env: &Env, record: &EscrowRecord) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Escrow(record.shipment_id), record);
+        env.storage().persistent().extend_ttl(
+            &DataKey::Escrow(record.shipment_id),
+            TTL_LEDGERS,
+            TTL_LEDGERS,
+        );
+        Self::reindex(env, record);
+    }
+
+    /// Bring the party, status and locked-amount indexes in line with
+    /// `record`.  Records never indexed (e.g. schema 1, via `migrate`) are
+    /// picked up as new.
+    fn reindex(env: &Env, record: &EscrowRecord) {
+        let key = DataKey::Indexed(record.shipment_id);
+        let previous: Option<IndexState> = env.storage().persistent().get(&key);
+        let status = record.status.kind();
+        let entry = (record.shipment_id, record.round);
+
+        let same_round = previous.as_ref().is_some_and(|p| p.round == record.round);
+        if !same_round {
+            Self::push_entry(
+                env,
+                IndexKey::Party(PartyRole::Shipper, record.shipper.clone()),
+                entry,
+            );
+            Self::push_entry(
+                env,
+                IndexKey::Party(PartyRole::Carrier, record.carrier.clone()),
+                entry,
+            );
+        }
+
+        let slot = match &previous {
+            Some(p) if p.status == status => p.slot,
+            _ => {
+                if let Some(p) = &previous {
+                    Self::add_status_count(env, p.status, -1);
+                }
+                Self::add_status_count(env, status, 1);
+                Self::push_entry(env, IndexKey::Status(status), entry)
+            }
+        };
+
+        // A new round only follows a settled one, which held nothing.
+        let locked = match record.status {
+            EscrowStatus::Funded | EscrowStatus::Disputed | EscrowStatus::Scheduled(_) => {
+                record.amount - record.released_amount
+            }
+            _ => 0,
+        };
+        let delta = match &previous {
+            Some(p) if same_round => locked - p.locked,
+            _ => locked,
+        };
+        if delta != 0 {
+            for (role, party) in [
+                (PartyRole::Shipper, &record.shipper),
+                (PartyRole::Carrier, &record.carrier),
+            ] {
+                let total = DataKey::Locked(role, party.clone(), record.token.clone());
+                Self::add_total(env, &total, delta);
+            }
+        }
+
+        // Every payout comes out of `deposited` up to the released amount, or
+        // all of `amount` once settled; what is left is still owed.
+        let held = record.deposited
+            - if Self::is_settled(&record.status) {
+                record.amount
+            } else {
+                record.released_amount
+            };
+        let delta = match &previous {
+            Some(p) if same_round => held - p.held,
+            _ => held,
+        };
+        if delta != 0 {
+            Self::add_total(env, &DataKey::Liabilities(record.token.clone()), delta);
+        }
+
+        env.storage().persistent().set(
+            &key,
+            &IndexState {
+                round: record.round,
+                status,
+                slot,
+                locked,
+                held,
+            },
+        );
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+    }
+
+    fn add_total(env: &Env, key: &DataKey, delta: i128) {
+        let total: i128 = env.storage().persistent().get(key).unwrap_or(0);
+        env.storage().persistent().set(key, &(total + delta));
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_LEDGERS, TTL_LEDGERS);
+    }
+
+    /// Append to an index list and return the entry's position.
+    fn push_entry(env: &Env, index: IndexKey, entry: (u64, u32)) -> u32 {
+        let len_key = DataKey::IndexLen(index.clone());
+        let position: u32 = env.storage().persistent().get(&len_key).unwrap_or(0);
+        let entry_key = DataKey::IndexEntry(index, position);
+        env.storage().persistent().set(&entry_key, &entry);
+        env.storage().persistent().set(&len_key, &(position + 1));
+        for key in [entry_key, len_key] {
+            env.storage()
+                .persistent()
+                .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+        }
+        position
+    }
+
+    fn add_status_count(env: &Env, status: StatusKind, delta: i32) {
+        let key = DataKey::StatusCount(status);
+        let count: u32 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&key, &count.saturating_add_signed(delta));
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+    }
+
+    /// Walk an index list backwards from `cursor`, scanning at most `limit`
+    /// entries.  Status entries count only while they are the escrow's live
+    /// slot.
+    fn page(env: &Env, index: IndexKey, cursor: Option<u32>, limit: u32) -> EscrowPage {
+        let len: u32 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::IndexLen(index.clone()))
+            .unwrap_or(0);
+        let limit = if limit == 0 || limit > MAX_PAGE_SIZE {
+            MAX_PAGE_SIZE
+        } else {
+            limit
+        };
+
+        let mut position = cursor.unwrap_or(len).min(len);
+        let mut records = Vec::new(env);
+        let mut scanned = 0;
+        while position > 0 && scanned < limit {
+            position -= 1;
+            scanned += 1;
+            let (shipment_id, round): (u64, u32) = env
+                .storage()
+                .persistent()
+                .get(&DataKey::IndexEntry(index.clone(), position))
+                .unwrap();
+            let record = match &index {
+                IndexKey::Party(..) => Self::get_escrow_round(env.clone(), shipment_id, round),
+                IndexKey::Status(status) => {
+                    let state: IndexState = env
+                        .storage()
+                        .persistent()
+                        .get(&DataKey::Indexed(shipment_id))
+                        .unwrap();
+                    if state.status != *status || state.slot != position {
+                        continue;
+                    }
+                    Self::load(env, shipment_id)
+                }
+            };
+            if let Ok(record) = record {
+                records.push_back(record);
+            }
+        }
+
+        EscrowPage {
+            records,
+            next_cursor: if position > 0 { Some(position) } else { None },
+        }
+    }
+
+    fn load_proposal(env: &Env, proposal_id: u64) -> Result<Proposal, EscrowError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Proposal(proposal_id))
+            .ok_or(EscrowError::ProposalNotFound)
+    }
+
+    fn store_proposal(env: &Env, proposal: &Proposal) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Proposal(proposal.id), proposal);
+        env.storage().persistent().extend_ttl(
+            &DataKey::Proposal(proposal.id),
+            TTL_LEDGERS,
+            TTL_LEDGERS,
+        );
+    }
+
+    fn next_proposal_id(env: &Env) -> u64 {
+        let current: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ProposalCounter)
+            .unwrap_or(0);
+        let next = current + 1;
+        env.storage()
+            .persistent()
+            .set(&DataKey::ProposalCounter, &next);
+        env.storage()
+            .persistent()
+            .extend_ttl(&DataKey::ProposalCounter, TTL_LEDGERS, TTL_LEDGERS);
+        next
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Events, Ledger},
+        token::{Client as TokenClient, StellarAssetClient},
+        vec, BytesN, Env,
+    };
+
+    /// Deploy a test SAC token, mint `amount` to `recipient`, return token address.
+    fn create_token(env: &Env, admin: &Address, recipient: &Address, amount: i128) -> Address {
+        let token_address = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let sac = StellarAssetClient::new(env, &token_address);
+        sac.mint(recipient, &amount);
+        token_address
+    }
+
+    fn setup(
+        shipper_balance: i128,
+    ) -> (
+        Env,
+        Address, // admin
+        Address, // shipper
+        Address, // carrier
+        Address, // token
+        EscrowContractClient<'static>,
+    ) {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let shipper = Address::generate(&env);
+        let carrier = Address::generate(&env);
+
+        let token_addr = create_token(&env, &admin, &shipper, shipper_balance);
+
+        let contract_id = env.register(EscrowContract {}, ());
+        let client = EscrowContractClient::new(&env, &contract_id);
+        client.initialize(&admin, &token_addr);
+
+        (env, admin, shipper, carrier, token_addr, client)
+    }
+
+    const AMOUNT: i128 = 500_000_000; // 50 XLM in stroops (7 decimals)
+    const SHIPMENT_ID: u64 = 42;
+
+    fn fund(
+        env: &Env,
+        token_addr: &Address,
+        client: &EscrowContractClient,
+        shipper: &Address,
+        carrier: &Address,
+    ) {
+        // Approve the escrow contract to pull AMOUNT from the shipper before funding.
+        let token = TokenClient::new(env, token_addr);
+        token.approve(
+            shipper,
+            &client.address,
+            &AMOUNT,
+            &(env.ledger().sequence() + 1000),
+        );
+        client.fund_escrow(shipper, carrier, &SHIPMENT_ID, token_addr, &AMOUNT);
+    }
+
+    #[test]
+    fn test_fund_and_release() {
+        let (env, _admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Funded);
+        assert_eq!(record.amount, AMOUNT);
+
+        // Check contract holds the tokens
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&client.address), AMOUNT);
+
+        // Release to carrier
+        client.release_payment(&SHIPMENT_ID);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Released);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+        assert_eq!(token.balance(&client.address), 0);
+    }
+
+    #[test]
+    fn test_fund_and_refund() {
+        let (env, _admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.refund_payment(&SHIPMENT_ID);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Refunded);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+    }
+
+    #[test]
+    fn test_dispute_resolved_to_carrier() {
+        let (env, _admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Disputed);
+
+        client.resolve_dispute(&SHIPMENT_ID, &true);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+    }
+
+    #[test]
+    fn test_dispute_resolved_to_shipper() {
+        let (env, _admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&carrier, &SHIPMENT_ID);
+        client.resolve_dispute(&SHIPMENT_ID, &false);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+    }
+
+    #[test]
+    fn test_double_fund_fails() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let result = client.try_fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT);
+        assert_eq!(result, Err(Ok(EscrowError::AlreadyFunded)));
+    }
+
+    #[test]
+    fn test_release_unfunded_fails() {
+        let (_, _, _, _, _, client) = setup(AMOUNT);
+
+        let result = client.try_release_payment(&SHIPMENT_ID);
+        assert_eq!(result, Err(Ok(EscrowError::NotFound)));
+    }
+
+    #[test]
+    fn test_invalid_amount_fails() {
+        let (_, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+
+        let result = client.try_fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &0i128);
+        assert_eq!(result, Err(Ok(EscrowError::InvalidAmount)));
+    }
+
+    #[test]
+    fn test_get_admin_returns_configured_admin() {
+        let (_env, admin, _shipper, _carrier, _token_addr, client) = setup(AMOUNT);
+        assert_eq!(client.get_admin(), admin);
+    }
+
+    #[test]
+    fn test_unauthorized_dispute_fails() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let random = Address::generate(&env);
+        let result = client.try_raise_dispute(&random, &SHIPMENT_ID);
+        assert_eq!(result, Err(Ok(EscrowError::Unauthorized)));
+    }
+
+    fn setup_multisig(env: &Env, client: &EscrowContractClient) -> (Address, Address, Address) {
+        let s1 = Address::generate(env);
+        let s2 = Address::generate(env);
+        let s3 = Address::generate(env);
+        client.set_signers(&vec![env, s1.clone(), s2.clone(), s3.clone()], &2);
+        (s1, s2, s3)
+    }
+
+    #[test]
+    fn test_multisig_release_after_threshold() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::Release);
+        assert_eq!(
+            client.try_execute_proposal(&id),
+            Err(Ok(EscrowError::ThresholdNotMet))
+        );
+
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Released
+        );
+
+        let proposal = client.get_proposal(&id);
+        assert!(proposal.executed);
+        assert_eq!(proposal.approvals.len(), 2);
+        assert_eq!(
+            client.try_execute_proposal(&id),
+            Err(Ok(EscrowError::ProposalExecuted))
+        );
+    }
+
+    #[test]
+    fn test_multisig_resolve_dispute() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let (_, s2, s3) = setup_multisig(&env, &client);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&carrier, &SHIPMENT_ID);
+
+        let id = client.propose_action(&s3, &SHIPMENT_ID, &EscrowAction::ResolveDispute(false));
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Refunded
+        );
+    }
+
+    #[test]
+    fn test_multisig_disables_single_admin_settlement() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        setup_multisig(&env, &client);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        assert_eq!(
+            client.try_release_payment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+        assert_eq!(
+            client.try_refund_payment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+    }
+
+    #[test]
+    fn test_multisig_duplicate_and_outsider_approvals_rejected() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let (s1, _, _) = setup_multisig(&env, &client);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::Refund);
+        assert_eq!(
+            client.try_approve_action(&s1, &id),
+            Err(Ok(EscrowError::AlreadyApproved))
+        );
+
+        let outsider = Address::generate(&env);
+        assert_eq!(
+            client.try_approve_action(&outsider, &id),
+            Err(Ok(EscrowError::NotSigner))
+        );
+        assert_eq!(
+            client.try_propose_action(&outsider, &SHIPMENT_ID, &EscrowAction::Refund),
+            Err(Ok(EscrowError::NotSigner))
+        );
+        assert_eq!(
+            client.try_approve_action(&s1, &99),
+            Err(Ok(EscrowError::ProposalNotFound))
+        );
+    }
+
+    #[test]
+    fn test_multisig_invalid_threshold() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        let s1 = Address::generate(&env);
+        let s2 = Address::generate(&env);
+
+        assert_eq!(
+            client.try_set_signers(&vec![&env, s1.clone(), s2.clone()], &3),
+            Err(Ok(EscrowError::InvalidThreshold))
+        );
+        assert_eq!(
+            client.try_set_signers(&vec![&env, s1.clone(), s2.clone()], &0),
+            Err(Ok(EscrowError::InvalidThreshold))
+        );
+        assert_eq!(
+            client.try_set_signers(&vec![&env, s1.clone(), s1.clone()], &2),
+            Err(Ok(EscrowError::InvalidThreshold))
+        );
+
+        client.set_signers(&vec![&env, s1, s2], &2);
+        assert_eq!(client.get_threshold(), 2);
+        assert_eq!(client.get_signers().len(), 2);
+    }
+
+    #[test]
+    fn test_multisig_removed_signer_approval_not_counted() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let (s1, s2, s3) = setup_multisig(&env, &client);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::Release);
+        client.approve_action(&s2, &id);
+
+        // s1 is rotated out before execution.
+        let rotation = EscrowAction::SetSigners(vec![&env, s2.clone(), s3.clone()], 2);
+        let rotate = client.propose_action(&s2, &0, &rotation);
+        client.approve_action(&s3, &rotate);
+        client.execute_proposal(&rotate);
+        assert_eq!(client.get_signers(), vec![&env, s2, s3]);
+        assert_eq!(
+            client.try_execute_proposal(&id),
+            Err(Ok(EscrowError::ThresholdNotMet))
+        );
+    }
+
+    #[test]
+    fn test_proposal_bound_to_its_round() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        let stale = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::Release);
+        client.approve_action(&s2, &stale);
+        assert_eq!(client.get_proposal(&stale).round, 1);
+
+        for action in [EscrowAction::Refund, EscrowAction::Reopen] {
+            let id = client.propose_action(&s1, &SHIPMENT_ID, &action);
+            client.approve_action(&s2, &id);
+            client.execute_proposal(&id);
+        }
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        assert_eq!(
+            client.try_execute_proposal(&stale),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).status, EscrowStatus::Funded);
+    }
+
+    #[test]
+    fn test_expired_proposal_is_gone() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::Release);
+        env.ledger()
+            .with_mut(|l| l.timestamp += PROPOSAL_LIFETIME + 1);
+        assert_eq!(
+            client.try_approve_action(&s2, &id),
+            Err(Ok(EscrowError::ProposalNotFound))
+        );
+        assert_eq!(
+            client.try_execute_proposal(&id),
+            Err(Ok(EscrowError::ProposalNotFound))
+        );
+    }
+
+    #[test]
+    fn test_admin_cannot_replace_signers_once_set() {
+        let (env, admin, _, _, _, client) = setup(AMOUNT);
+        let (s1, _, _) = setup_multisig(&env, &client);
+
+        assert_eq!(
+            client.try_set_signers(&vec![&env, admin.clone()], &1),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+        // One signer cannot rotate the set alone either.
+        let id = client.propose_action(&s1, &0, &EscrowAction::SetSigners(vec![&env, admin], 1));
+        assert_eq!(
+            client.try_execute_proposal(&id),
+            Err(Ok(EscrowError::ThresholdNotMet))
+        );
+        assert_eq!(client.get_threshold(), 2);
+    }
+
+    const DELAY: u64 = 86_400; // 24h challenge window
+
+    #[test]
+    fn test_shorter_delay_waits_out_the_current_one() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_settlement_delay(&DELAY);
+        client.set_settlement_delay(&0);
+        assert_eq!(client.get_settlement_delay(), DELAY);
+        assert_eq!(
+            client.get_pending_delay(),
+            Some(PendingDelay {
+                delay_seconds: 0,
+                effective_at: env.ledger().timestamp() + DELAY,
+            })
+        );
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.release_payment(&SHIPMENT_ID);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Scheduled(SettlementKind::Release)
+        );
+
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        assert_eq!(client.get_settlement_delay(), 0);
+        client.execute_settlement(&SHIPMENT_ID);
+
+        // Raising the window again applies at once and drops the pending cut.
+        client.set_settlement_delay(&DELAY);
+        assert_eq!(client.get_settlement_delay(), DELAY);
+        assert_eq!(client.get_pending_delay(), None);
+    }
+
+    #[test]
+    fn test_timelocked_release() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_settlement_delay(&DELAY);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.release_payment(&SHIPMENT_ID);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(
+            record.status,
+            EscrowStatus::Scheduled(SettlementKind::Release)
+        );
+        assert_eq!(record.execute_after, env.ledger().timestamp() + DELAY);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), 0);
+        assert_eq!(
+            client.try_execute_settlement(&SHIPMENT_ID),
+            Err(Ok(EscrowError::TimelockActive))
+        );
+
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        client.execute_settlement(&SHIPMENT_ID);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Released);
+        assert_eq!(record.execute_after, 0);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+    }
+
+    #[test]
+    fn test_timelocked_refund() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_settlement_delay(&DELAY);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.refund_payment(&SHIPMENT_ID);
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        client.execute_settlement(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Refunded
+        );
+    }
+
+    #[test]
+    fn test_dispute_cancels_scheduled_settlement() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_settlement_delay(&DELAY);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.release_payment(&SHIPMENT_ID);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Disputed);
+        assert_eq!(record.execute_after, 0);
+
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        assert_eq!(
+            client.try_execute_settlement(&SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&client.address), AMOUNT);
+    }
+
+    #[test]
+    fn test_execute_unscheduled_fails() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        assert_eq!(
+            client.try_execute_settlement(&SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
+    }
+
+    #[test]
+    fn test_multisig_release_is_timelocked() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        client.set_settlement_delay(&DELAY);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::Release);
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Scheduled(SettlementKind::Release)
+        );
+
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        client.execute_settlement(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+    }
+
+    /// Events published by the escrow contract itself (token events filtered out).
+    fn escrow_events(env: &Env, client: &EscrowContractClient) -> Vec<(Address, Vec<Val>, Val)> {
+        let mut events = Vec::new(env);
+        for event in env.events().all().iter() {
+            if event.0 == client.address {
+                events.push_back(event);
+            }
+        }
+        events
+    }
+
+    fn topics(env: &Env, name: &str) -> Vec<Val> {
+        (EVENT_NS, Symbol::new(env, name), EVENT_VERSION).into_val(env)
+    }
+
+    #[test]
+    fn test_fund_emits_event() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "funded"),
+                    FundedEvent {
+                        shipment_id: SHIPMENT_ID,
+                        round: 1,
+                        shipper: shipper.clone(),
+                        payer: shipper.clone(),
+                        carrier: carrier.clone(),
+                        token: token_addr.clone(),
+                        amount: AMOUNT,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_release_and_refund_emit_events() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.release_payment(&SHIPMENT_ID);
+
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "released"),
+                    SettledEvent {
+                        shipment_id: SHIPMENT_ID,
+                        recipient: carrier.clone(),
+                        amount: AMOUNT,
+                        fee: 0,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.refund_payment(&SHIPMENT_ID);
+
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "refunded"),
+                    SettledEvent {
+                        shipment_id: SHIPMENT_ID,
+                        recipient: shipper.clone(),
+                        amount: AMOUNT,
+                        fee: 0,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedule_and_dispute_emit_events() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_settlement_delay(&DELAY);
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "delay"),
+                    DelayEvent {
+                        delay_seconds: DELAY
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.release_payment(&SHIPMENT_ID);
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "scheduled"),
+                    ScheduledEvent {
+                        shipment_id: SHIPMENT_ID,
+                        kind: SettlementKind::Release,
+                        execute_after: env.ledger().timestamp() + DELAY,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.raise_dispute(&carrier, &SHIPMENT_ID);
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "disputed"),
+                    DisputedEvent {
+                        shipment_id: SHIPMENT_ID,
+                        raised_by: carrier.clone(),
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multisig_emits_events() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let s1 = Address::generate(&env);
+        let s2 = Address::generate(&env);
+        let signers = vec![&env, s1.clone(), s2.clone()];
+        client.set_signers(&signers, &2);
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "signers"),
+                    SignersEvent {
+                        signers,
+                        threshold: 2
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::Release);
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "proposed"),
+                    ProposalEvent {
+                        proposal_id: id,
+                        shipment_id: SHIPMENT_ID,
+                        signer: s1.clone(),
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.approve_action(&s2, &id);
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "approved"),
+                    ProposalEvent {
+                        proposal_id: id,
+                        shipment_id: SHIPMENT_ID,
+                        signer: s2.clone(),
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.execute_proposal(&id);
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "released"),
+                    SettledEvent {
+                        shipment_id: SHIPMENT_ID,
+                        recipient: carrier.clone(),
+                        amount: AMOUNT,
+                        fee: 0,
+                    }
+                    .into_val(&env),
+                ),
+                (
+                    client.address.clone(),
+                    topics(&env, "executed"),
+                    ExecutedEvent {
+                        proposal_id: id,
+                        shipment_id: SHIPMENT_ID,
+                        action: EscrowAction::Release,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause_funding_only() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+        let pauser = Address::generate(&env);
+        client.set_pauser(&pauser);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.pause(&pauser, &PauseScope::Funding);
+        assert_eq!(
+            client.get_pause_state(),
+            PauseState {
+                funding: true,
+                settlement: false
+            }
+        );
+        assert_eq!(
+            client.try_fund_escrow(&shipper, &carrier, &7, &token_addr, &AMOUNT),
+            Err(Ok(EscrowError::Paused))
+        );
+
+        // Settlement is unaffected.
+        client.release_payment(&SHIPMENT_ID);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Released
+        );
+    }
+
+    #[test]
+    fn test_pause_settlement_keeps_reads_working() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let pauser = Address::generate(&env);
+        client.set_pauser(&pauser);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.pause(&pauser, &PauseScope::Settlement);
+        assert_eq!(
+            client.try_release_payment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::Paused))
+        );
+        assert_eq!(
+            client.try_refund_payment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::Paused))
+        );
+        assert_eq!(
+            client.try_execute_settlement(&SHIPMENT_ID),
+            Err(Ok(EscrowError::Paused))
+        );
+
+        // Disputes stay open unless everything is paused.
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        assert_eq!(
+            client.try_resolve_dispute(&SHIPMENT_ID, &true),
+            Err(Ok(EscrowError::Paused))
+        );
+
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Disputed
+        );
+        assert_eq!(client.get_balance(&token_addr), AMOUNT);
+
+        client.unpause(&PauseScope::Settlement);
+        client.resolve_dispute(&SHIPMENT_ID, &true);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Released
+        );
+    }
+
+    #[test]
+    fn test_pause_all_freezes_disputes() {
+        let (env, admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.pause(&admin, &PauseScope::All);
+        assert_eq!(
+            client.try_raise_dispute(&shipper, &SHIPMENT_ID),
+            Err(Ok(EscrowError::Paused))
+        );
+
+        client.unpause(&PauseScope::All);
+        assert_eq!(client.get_pause_state(), PauseState::default());
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+    }
+
+    #[test]
+    fn test_pause_requires_pauser_or_admin() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        let pauser = Address::generate(&env);
+        let random = Address::generate(&env);
+        client.set_pauser(&pauser);
+        assert_eq!(client.get_pauser(), Some(pauser));
+
+        assert_eq!(
+            client.try_pause(&random, &PauseScope::All),
+            Err(Ok(EscrowError::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_unpause_requires_admin() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        let pauser = Address::generate(&env);
+        client.set_pauser(&pauser);
+        client.pause(&pauser, &PauseScope::All);
+
+        env.mock_auths(&[]);
+        assert!(client.try_unpause(&PauseScope::All).is_err());
+    }
+
+    fn fund_milestones(
+        env: &Env,
+        token_addr: &Address,
+        client: &EscrowContractClient,
+        shipper: &Address,
+        carrier: &Address,
+    ) {
+        let token = TokenClient::new(env, token_addr);
+        token.approve(
+            shipper,
+            &client.address,
+            &AMOUNT,
+            &(env.ledger().sequence() + 1000),
+        );
+        // Lagos → Nairobi: 20% on pickup, 30% at the border, 50% on delivery.
+        let schedule = vec![
+            env,
+            (symbol_short!("pickup"), 2_000u32),
+            (symbol_short!("border"), 3_000u32),
+            (symbol_short!("delivery"), 5_000u32),
+        ];
+        client.fund_escrow_with_milestones(
+            shipper,
+            carrier,
+            &SHIPMENT_ID,
+            token_addr,
+            &AMOUNT,
+            &schedule,
+        );
+    }
+
+    #[test]
+    fn test_milestones_released_independently() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+        let token = TokenClient::new(&env, &token_addr);
+
+        // Border checkpoint cleared before the pickup tranche is paid.
+        client.release_milestone(&SHIPMENT_ID, &1);
+        assert_eq!(token.balance(&carrier), AMOUNT * 30 / 100);
+
+        client.release_milestone(&SHIPMENT_ID, &0);
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Funded);
+        assert_eq!(record.released_amount, AMOUNT / 2);
+        assert!(record.milestones.get(0).unwrap().released);
+        assert!(!record.milestones.get(2).unwrap().released);
+
+        client.release_milestone(&SHIPMENT_ID, &2);
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Released);
+        assert_eq!(record.released_amount, AMOUNT);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+        assert_eq!(token.balance(&client.address), 0);
+    }
+
+    #[test]
+    fn test_milestone_refund_returns_only_remainder() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.release_milestone(&SHIPMENT_ID, &0);
+        client.refund_payment(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT * 20 / 100);
+        assert_eq!(token.balance(&shipper), AMOUNT * 80 / 100);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Refunded);
+        assert_eq!(record.released_amount, AMOUNT * 20 / 100);
+    }
+
+    #[test]
+    fn test_milestone_full_release_pays_remainder() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.release_milestone(&SHIPMENT_ID, &0);
+        client.release_payment(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert!(record.milestones.iter().all(|m| m.released));
+    }
+
+    #[test]
+    fn test_milestone_errors() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+
+        let bad = vec![
+            &env,
+            (symbol_short!("pickup"), 2_000u32),
+            (symbol_short!("delivery"), 5_000u32),
+        ];
+        assert_eq!(
+            client.try_fund_escrow_with_milestones(
+                &shipper,
+                &carrier,
+                &SHIPMENT_ID,
+                &token_addr,
+                &AMOUNT,
+                &bad
+            ),
+            Err(Ok(EscrowError::InvalidMilestones))
+        );
+
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+        client.release_milestone(&SHIPMENT_ID, &0);
+        assert_eq!(
+            client.try_release_milestone(&SHIPMENT_ID, &0),
+            Err(Ok(EscrowError::MilestoneAlreadyReleased))
+        );
+        assert_eq!(
+            client.try_release_milestone(&SHIPMENT_ID, &3),
+            Err(Ok(EscrowError::MilestoneNotFound))
+        );
+    }
+
+    #[test]
+    fn test_milestone_release_is_timelocked() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_settlement_delay(&DELAY);
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.release_milestone(&SHIPMENT_ID, &0);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Scheduled(SettlementKind::Milestone(0))
+        );
+
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        client.execute_settlement(&SHIPMENT_ID);
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "milestone"),
+                    MilestoneEvent {
+                        shipment_id: SHIPMENT_ID,
+                        index: 0,
+                        label: symbol_short!("pickup"),
+                        amount: AMOUNT * 20 / 100,
+                        fee: 0,
+                        remaining: AMOUNT * 80 / 100,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT * 20 / 100);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).status, EscrowStatus::Funded);
+    }
+
+    #[test]
+    fn test_split_resolution_with_arbitration_fee() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let arbiter = Address::generate(&env);
+        client.set_arbitration_treasury(&arbiter);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+
+        let ruling = DisputeRuling {
+            carrier_bps: 6_000,
+            fee_bps: 500,
+            fee_recipient: Some(arbiter.clone()),
+            slash_bps: 0,
+        };
+        // A ruling that takes a fee needs the signers.
+        assert_eq!(
+            client.try_resolve_dispute_split(&SHIPMENT_ID, &ruling),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        let id = client.propose_action(
+            &s1,
+            &SHIPMENT_ID,
+            &EscrowAction::ResolveSplit(ruling.clone()),
+        );
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+
+        // 5% fee = 25 XLM; carrier 60% of 475 = 285; shipper 190.
+        let amounts = SplitAmounts {
+            carrier: 285_000_000,
+            shipper: 190_000_000,
+            fee: 25_000_000,
+        };
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "resolved"),
+                    ResolvedEvent {
+                        shipment_id: SHIPMENT_ID,
+                        ruling: ruling.clone(),
+                        amounts: amounts.clone(),
+                    }
+                    .into_val(&env),
+                ),
+                (
+                    client.address.clone(),
+                    topics(&env, "executed"),
+                    ExecutedEvent {
+                        proposal_id: id,
+                        shipment_id: SHIPMENT_ID,
+                        action: EscrowAction::ResolveSplit(ruling.clone()),
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), amounts.carrier);
+        assert_eq!(token.balance(&shipper), amounts.shipper);
+        assert_eq!(token.balance(&arbiter), amounts.fee);
+        assert_eq!(token.balance(&client.address), 0);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Split);
+        assert_eq!(record.resolution, Resolution::Ruled(ruling, amounts));
+    }
+
+    #[test]
+    fn test_split_resolution_after_milestone_uses_remainder() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+        client.release_milestone(&SHIPMENT_ID, &0); // 100 XLM to carrier
+        client.raise_dispute(&carrier, &SHIPMENT_ID);
+
+        client.resolve_dispute_split(
+            &SHIPMENT_ID,
+            &DisputeRuling {
+                carrier_bps: 5_000,
+                fee_bps: 0,
+                fee_recipient: None,
+                slash_bps: 0,
+            },
+        );
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), 100_000_000 + 200_000_000);
+        assert_eq!(token.balance(&shipper), 200_000_000);
+    }
+
+    #[test]
+    fn test_all_or_nothing_resolution_is_recorded() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        client.resolve_dispute(&SHIPMENT_ID, &false);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Refunded);
+        assert_eq!(
+            record.resolution,
+            Resolution::Ruled(
+                DisputeRuling {
+                    carrier_bps: 0,
+                    fee_bps: 0,
+                    fee_recipient: None,
+                    slash_bps: 0,
+                },
+                SplitAmounts {
+                    carrier: 0,
+                    shipper: AMOUNT,
+                    fee: 0,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_ruling_rejected() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+
+        let over = DisputeRuling {
+            carrier_bps: 10_001,
+            fee_bps: 0,
+            fee_recipient: None,
+            slash_bps: 0,
+        };
+        assert_eq!(
+            client.try_resolve_dispute_split(&SHIPMENT_ID, &over),
+            Err(Ok(EscrowError::InvalidRuling))
+        );
+
+        let treasury = Address::generate(&env);
+        client.set_arbitration_treasury(&treasury);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        let rejected = |fee_bps: u32, fee_recipient: Option<Address>| {
+            let ruling = DisputeRuling {
+                carrier_bps: 5_000,
+                fee_bps,
+                fee_recipient,
+                slash_bps: 0,
+            };
+            let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::ResolveSplit(ruling));
+            client.approve_action(&s2, &id);
+            client.try_execute_proposal(&id) == Err(Ok(EscrowError::InvalidRuling))
+        };
+        // No recipient, someone other than the treasury, or above the cap.
+        assert!(rejected(100, None));
+        assert!(rejected(100, Some(Address::generate(&env))));
+        assert!(rejected(
+            MAX_ARBITRATION_FEE_BPS + 1,
+            Some(treasury.clone())
+        ));
+        assert!(!rejected(MAX_ARBITRATION_FEE_BPS, Some(treasury)));
+    }
+
+    #[test]
+    fn test_arbitration_treasury_needs_signers() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        let treasury = Address::generate(&env);
+
+        assert_eq!(
+            client.try_set_arbitration_treasury(&treasury),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+        let id = client.propose_action(
+            &s1,
+            &0,
+            &EscrowAction::SetArbitrationTreasury(treasury.clone()),
+        );
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+        assert_eq!(client.get_arbitration_treasury(), Some(treasury));
+    }
+
+    #[test]
+    fn test_multisig_split_resolution() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&carrier, &SHIPMENT_ID);
+
+        let ruling = DisputeRuling {
+            carrier_bps: 2_500,
+            fee_bps: 0,
+            fee_recipient: None,
+            slash_bps: 0,
+        };
+        let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::ResolveSplit(ruling));
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT / 4);
+        assert_eq!(token.balance(&shipper), AMOUNT * 3 / 4);
+    }
+
+    #[test]
+    fn test_fund_in_second_allowed_token() {
+        let (env, admin, shipper, carrier, xlm, client) = setup(AMOUNT);
+        let usdc = create_token(&env, &admin, &shipper, AMOUNT);
+        TokenClient::new(&env, &usdc).approve(
+            &shipper,
+            &client.address,
+            &AMOUNT,
+            &(env.ledger().sequence() + 1000),
+        );
+
+        assert_eq!(
+            client.try_fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &usdc, &AMOUNT),
+            Err(Ok(EscrowError::TokenNotAllowed))
+        );
+
+        client.allow_token(&usdc);
+        assert_eq!(
+            client.get_allowed_tokens(),
+            vec![&env, xlm.clone(), usdc.clone()]
+        );
+        client.fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &usdc, &AMOUNT);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).token, usdc);
+        assert_eq!(client.get_balance(&usdc), AMOUNT);
+        assert_eq!(client.get_balance(&xlm), 0);
+
+        // Delisting stops new escrows, but the open one still settles in USDC.
+        client.disallow_token(&usdc);
+        client.release_payment(&SHIPMENT_ID);
+        assert_eq!(TokenClient::new(&env, &usdc).balance(&carrier), AMOUNT);
+        assert_eq!(TokenClient::new(&env, &xlm).balance(&carrier), 0);
+    }
+
+    #[test]
+    fn test_disallow_unknown_token_fails() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        assert_eq!(
+            client.try_disallow_token(&Address::generate(&env)),
+            Err(Ok(EscrowError::TokenNotAllowed))
+        );
+    }
+
+    #[test]
+    fn test_release_deducts_commission() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let treasury = Address::generate(&env);
+        client.set_commission(&250, &treasury, &false);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.release_payment(&SHIPMENT_ID);
+
+        let fee = AMOUNT * 250 / 10_000;
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "released"),
+                    SettledEvent {
+                        shipment_id: SHIPMENT_ID,
+                        recipient: carrier.clone(),
+                        amount: AMOUNT - fee,
+                        fee,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT - fee);
+        assert_eq!(token.balance(&treasury), fee);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).fee_charged, fee);
+        assert_eq!(client.get_accrued_fees(&token_addr), fee);
+    }
+
+    #[test]
+    fn test_shipper_override_applies_per_milestone() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let treasury = Address::generate(&env);
+        client.set_commission(&250, &treasury, &false);
+        client.set_shipper_commission(&shipper, &Some(100));
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.release_milestone(&SHIPMENT_ID, &0);
+        client.release_payment(&SHIPMENT_ID);
+
+        // 1% of the 20% tranche, then 1% of the remaining 80%.
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&treasury), AMOUNT / 100);
+        assert_eq!(token.balance(&carrier), AMOUNT - AMOUNT / 100);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).fee_charged, AMOUNT / 100);
+
+        client.set_shipper_commission(&shipper, &None);
+        assert_eq!(client.get_shipper_commission(&shipper), None);
+    }
+
+    #[test]
+    fn test_refund_charged_only_when_configured() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let treasury = Address::generate(&env);
+        let token = TokenClient::new(&env, &token_addr);
+
+        client.set_commission(&250, &treasury, &false);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.refund_payment(&SHIPMENT_ID);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).fee_charged, 0);
+
+        client.set_commission(&250, &treasury, &true);
+        client.reopen_escrow(&SHIPMENT_ID);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.refund_payment(&SHIPMENT_ID);
+        let fee = AMOUNT * 250 / 10_000;
+        assert_eq!(token.balance(&shipper), AMOUNT - fee);
+        assert_eq!(client.get_accrued_fees(&token_addr), fee);
+    }
+
+    #[test]
+    fn test_commission_above_cap_rejected() {
+        let (env, _, shipper, _, _, client) = setup(AMOUNT);
+        let treasury = Address::generate(&env);
+        assert_eq!(
+            client.try_set_commission(&(MAX_COMMISSION_BPS + 1), &treasury, &false),
+            Err(Ok(EscrowError::InvalidCommission))
+        );
+        assert_eq!(
+            client.try_set_shipper_commission(&shipper, &Some(MAX_COMMISSION_BPS + 1)),
+            Err(Ok(EscrowError::InvalidCommission))
+        );
+        client.set_commission(&MAX_COMMISSION_BPS, &treasury, &false);
+    }
+
+    #[test]
+    fn test_commission_needs_signers() {
+        let (env, _, shipper, _, _, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        let treasury = Address::generate(&env);
+
+        assert_eq!(
+            client.try_set_commission(&250, &treasury, &false),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+        assert_eq!(
+            client.try_set_shipper_commission(&shipper, &Some(100)),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+
+        let id = client.propose_action(
+            &s1,
+            &0,
+            &EscrowAction::SetCommission(250, treasury.clone(), false),
+        );
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+        let id = client.propose_action(
+            &s1,
+            &0,
+            &EscrowAction::SetShipperCommission(shipper.clone(), Some(100)),
+        );
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+
+        assert_eq!(
+            client.get_commission(),
+            Some(CommissionConfig {
+                bps: 250,
+                treasury,
+                charge_on_refund: false,
+            })
+        );
+        assert_eq!(client.get_shipper_commission(&shipper), Some(100));
+    }
+
+    #[test]
+    fn test_scheduled_release_keeps_its_commission() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let treasury = Address::generate(&env);
+        client.set_commission(&100, &treasury, &false);
+        client.set_settlement_delay(&DELAY);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.release_payment(&SHIPMENT_ID);
+
+        // Raising the rate inside the challenge window does not reach it.
+        client.set_commission(&MAX_COMMISSION_BPS, &treasury, &false);
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        client.execute_settlement(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&treasury), AMOUNT / 100);
+        assert_eq!(token.balance(&carrier), AMOUNT - AMOUNT / 100);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).commission,
+            CommissionTerms::Uncharged
+        );
+    }
+
+    #[test]
+    fn test_cancel_splits_per_fee_table() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_cancellation_fees(&CancellationFees {
+            pending: 0,
+            accepted: 1_000,
+            in_transit: 2_500,
+        });
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::InTransit,
+        );
+
+        client.cancel_escrow(&SHIPMENT_ID);
+
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "cancelled"),
+                    CancelledEvent {
+                        shipment_id: SHIPMENT_ID,
+                        stage: CancelStage::InTransit,
+                        refund: AMOUNT * 3 / 4,
+                        compensation: AMOUNT / 4,
+                        fee: 0,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT * 3 / 4);
+        assert_eq!(token.balance(&carrier), AMOUNT / 4);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Refunded
+        );
+    }
+
+    #[test]
+    fn test_cancel_stage_comes_from_linked_shipment() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_cancellation_fees(&CancellationFees {
+            pending: 0,
+            accepted: 1_000,
+            in_transit: 2_500,
+        });
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        assert_eq!(
+            client.try_cancel_escrow(&SHIPMENT_ID),
+            Err(Ok(EscrowError::ShipmentContractNotSet))
+        );
+
+        // The carrier accepted, then the shipper cancelled.
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Cancelled,
+        );
+        client.cancel_escrow(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT / 10);
+        assert_eq!(token.balance(&shipper), AMOUNT * 9 / 10);
+    }
+
+    #[test]
+    fn test_refund_goes_through_fee_table() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_cancellation_fees(&CancellationFees {
+            pending: 0,
+            accepted: 1_000,
+            in_transit: 2_500,
+        });
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::InTransit,
+        );
+
+        client.refund_payment(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT / 4);
+        assert_eq!(token.balance(&shipper), AMOUNT * 3 / 4);
+    }
+
+    #[test]
+    fn test_cancel_without_fee_table_refunds_in_full() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Accepted,
+        );
+
+        client.cancel_escrow(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(token.balance(&carrier), 0);
+    }
+
+    #[test]
+    fn test_timelocked_cancel_uses_table_at_execution() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_settlement_delay(&DELAY);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Accepted,
+        );
+
+        client.cancel_escrow(&SHIPMENT_ID);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Scheduled(SettlementKind::Cancel(CancelStage::Accepted))
+        );
+
+        client.set_cancellation_fees(&CancellationFees {
+            pending: 0,
+            accepted: 1_000,
+            in_transit: 2_500,
+        });
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        client.execute_settlement(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT / 10);
+        assert_eq!(token.balance(&shipper), AMOUNT * 9 / 10);
+    }
+
+    #[test]
+    fn test_invalid_fee_table_rejected() {
+        let (_, _, _, _, _, client) = setup(AMOUNT);
+        assert_eq!(
+            client.try_set_cancellation_fees(&CancellationFees {
+                pending: 0,
+                accepted: 10_001,
+                in_transit: 0,
+            }),
+            Err(Ok(EscrowError::InvalidFeeTable))
+        );
+    }
+
+    /// Stand-in for the shipment contract: serves whatever `put` stored.
+    #[contract]
+    struct MockShipments;
+
+    #[contractimpl]
+    impl MockShipments {
+        pub fn put(env: Env, shipment: Shipment) {
+            env.storage().instance().set(&shipment.id, &shipment);
+        }
+
+        pub fn get_shipment(env: Env, shipment_id: u64) -> Shipment {
+            env.storage().instance().get(&shipment_id).unwrap()
+        }
+
+        pub fn put_legs(env: Env, shipment_id: u64, legs: Vec<Leg>) {
+            env.storage()
+                .instance()
+                .set(&(symbol_short!("legs"), shipment_id), &legs);
+        }
+
+        pub fn get_legs(env: Env, shipment_id: u64) -> Vec<Leg> {
+            env.storage()
+                .instance()
+                .get(&(symbol_short!("legs"), shipment_id))
+                .unwrap_or_else(|| Vec::new(&env))
+        }
+
+        pub fn put_ruling(env: Env, shipment_id: u64, carrier_bps: u32) {
+            env.storage()
+                .instance()
+                .set(&(symbol_short!("ruling"), shipment_id), &carrier_bps);
+        }
+
+        pub fn get_dispute_ruling(env: Env, shipment_id: u64) -> Option<u32> {
+            env.storage()
+                .instance()
+                .get(&(symbol_short!("ruling"), shipment_id))
+        }
+    }
+
+    fn link_shipments(
+        env: &Env,
+        client: &EscrowContractClient,
+        shipper: &Address,
+        carrier: Option<Address>,
+        status: ShipmentStatus,
+    ) -> MockShipmentsClient<'static> {
+        let shipments = MockShipmentsClient::new(env, &env.register(MockShipments, ()));
+        client.set_shipment_contract(&shipments.address);
+        let text = String::from_str(env, "Lagos");
+        shipments.put(&Shipment {
+            id: SHIPMENT_ID,
+            shipper: shipper.clone(),
+            carrier,
+            origin: text.clone(),
+            destination: text.clone(),
+            cargo_description: text,
+            weight_kg: 1_000,
+            price: AMOUNT,
+            status,
+            created_at: 0,
+            updated_at: 0,
+            deliver_by: 0,
+            penalty: LatePenalty::default(),
+            picked_up_at: 0,
+            delivered_at: 0,
+        });
+        shipments
+    }
+
+    #[test]
+    fn test_settle_from_completed_shipment_pays_carrier() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        assert_eq!(
+            client.try_settle_from_shipment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::ShipmentContractNotSet))
+        );
+
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::InTransit,
+        );
+        assert_eq!(
+            client.try_settle_from_shipment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::ShipmentNotSettled))
+        );
+
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::Completed;
+        shipments.put(&shipment);
+
+        // Anyone may trigger it — no admin auth is required.
+        env.mock_auths(&[]);
+        client.settle_from_shipment(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Released
+        );
+    }
+
+    #[test]
+    fn test_settle_from_cancelled_shipment_refunds_shipper() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(&env, &client, &shipper, None, ShipmentStatus::Cancelled);
+
+        client.settle_from_shipment(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Refunded
+        );
+    }
+
+    #[test]
+    fn test_settle_from_shipment_rejects_other_carrier() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(Address::generate(&env)),
+            ShipmentStatus::Completed,
+        );
+
+        assert_eq!(
+            client.try_settle_from_shipment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::PartyMismatch))
+        );
+    }
+
+    const CONFIRM_WINDOW: u64 = 3 * 86_400;
+
+    #[test]
+    fn test_carrier_claims_after_confirmation_deadline() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Delivered,
+        );
+
+        assert_eq!(
+            client.try_claim_unconfirmed(&carrier, &SHIPMENT_ID),
+            Err(Ok(EscrowError::AutoReleaseDisabled))
+        );
+
+        client.set_confirmation_window(&CONFIRM_WINDOW);
+        env.ledger().with_mut(|l| l.timestamp += CONFIRM_WINDOW - 1);
+        assert_eq!(
+            client.try_claim_unconfirmed(&carrier, &SHIPMENT_ID),
+            Err(Ok(EscrowError::DeadlineNotReached))
+        );
+
+        env.ledger().with_mut(|l| l.timestamp += 1);
+        client.claim_unconfirmed(&carrier, &SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Released
+        );
+    }
+
+    #[test]
+    fn test_claim_window_runs_from_delivery() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_confirmation_window(&CONFIRM_WINDOW);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Delivered,
+        );
+        // Delivered at 1 000; a later update must not restart the window.
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.delivered_at = 1_000;
+        shipment.updated_at = 1_000 + CONFIRM_WINDOW;
+        shipments.put(&shipment);
+
+        env.ledger()
+            .with_mut(|l| l.timestamp = 1_000 + CONFIRM_WINDOW);
+        client.claim_unconfirmed(&carrier, &SHIPMENT_ID);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&carrier),
+            AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_dispute_before_deadline_blocks_claim() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_confirmation_window(&CONFIRM_WINDOW);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Delivered,
+        );
+
+        // Shipper disputes on both contracts a day into the window.
+        env.ledger().with_mut(|l| l.timestamp += 86_400);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::Disputed;
+        shipments.put(&shipment);
+
+        env.ledger().with_mut(|l| l.timestamp += CONFIRM_WINDOW);
+        assert_eq!(
+            client.try_claim_unconfirmed(&carrier, &SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&client.address),
+            AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_only_escrow_carrier_can_claim() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_confirmation_window(&CONFIRM_WINDOW);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Delivered,
+        );
+        env.ledger().with_mut(|l| l.timestamp += CONFIRM_WINDOW);
+
+        assert_eq!(
+            client.try_claim_unconfirmed(&shipper, &SHIPMENT_ID),
+            Err(Ok(EscrowError::Unauthorized))
+        );
+    }
+
+    const ACCEPT_WITHIN: u64 = 2 * 86_400;
+    const PICKUP_WITHIN: u64 = 5 * 86_400;
+
+    fn fund_with_deadlines(
+        env: &Env,
+        token_addr: &Address,
+        client: &EscrowContractClient,
+        shipper: &Address,
+        carrier: &Address,
+    ) {
+        client.set_deadline_policy(&DeadlinePolicy {
+            accept_within: ACCEPT_WITHIN,
+            pickup_within: PICKUP_WITHIN,
+        });
+        fund(env, token_addr, client, shipper, carrier);
+    }
+
+    #[test]
+    fn test_shipper_reclaims_when_nobody_accepts() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_with_deadlines(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(&env, &client, &shipper, None, ShipmentStatus::Created);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.accept_by, record.funded_at + ACCEPT_WITHIN);
+
+        env.ledger().with_mut(|l| l.timestamp += ACCEPT_WITHIN);
+        assert_eq!(
+            client.try_reclaim_expired(&shipper, &SHIPMENT_ID),
+            Err(Ok(EscrowError::DeadlineNotReached))
+        );
+
+        env.ledger().with_mut(|l| l.timestamp += 1);
+        client.reclaim_expired(&shipper, &SHIPMENT_ID);
+
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&shipper),
+            AMOUNT
+        );
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Refunded
+        );
+    }
+
+    #[test]
+    fn test_shipper_reclaims_when_accepted_but_not_picked_up() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_with_deadlines(&env, &token_addr, &client, &shipper, &carrier);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Accepted,
+        );
+
+        // Accepted in time, so the acceptance deadline no longer applies.
+        env.ledger().with_mut(|l| l.timestamp += ACCEPT_WITHIN + 1);
+        assert_eq!(
+            client.try_reclaim_expired(&shipper, &SHIPMENT_ID),
+            Err(Ok(EscrowError::DeadlineNotReached))
+        );
+
+        env.ledger().with_mut(|l| l.timestamp += PICKUP_WITHIN);
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::InTransit;
+        shipments.put(&shipment);
+        assert_eq!(
+            client.try_reclaim_expired(&shipper, &SHIPMENT_ID),
+            Err(Ok(EscrowError::DeadlineNotReached))
+        );
+
+        shipment.status = ShipmentStatus::Accepted;
+        shipments.put(&shipment);
+        client.reclaim_expired(&shipper, &SHIPMENT_ID);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&shipper),
+            AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_reclaim_requires_deadlines() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        link_shipments(&env, &client, &shipper, None, ShipmentStatus::Created);
+
+        assert_eq!(
+            client.try_reclaim_expired(&shipper, &SHIPMENT_ID),
+            Err(Ok(EscrowError::NoDeadlineSet))
+        );
+    }
+
+    fn deliver_late(shipments: &MockShipmentsClient, hours_late: u64) {
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::Completed;
+        shipment.deliver_by = 86_400;
+        shipment.penalty = LatePenalty {
+            bps_per_hour: 100,
+            max_bps: 1_500,
+        };
+        shipment.delivered_at = 86_400 + hours_late * 3_600 + 59;
+        shipments.put(&shipment);
+    }
+
+    #[test]
+    fn test_late_delivery_penalty_refunded_to_shipper() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Completed,
+        );
+        deliver_late(&shipments, 4);
+
+        client.settle_from_shipment(&SHIPMENT_ID);
+
+        // 4 full hours × 1% = 4%.
+        let penalty = AMOUNT * 4 / 100;
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT - penalty);
+        assert_eq!(token.balance(&shipper), penalty);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).penalty, penalty);
+    }
+
+    #[test]
+    fn test_late_penalty_taken_from_final_milestone() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Completed,
+        );
+        deliver_late(&shipments, 4);
+
+        client.release_milestone(&SHIPMENT_ID, &0);
+        client.release_milestone(&SHIPMENT_ID, &1);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT / 2);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).penalty, 0);
+
+        // 4% of the 50% delivery tranche goes back to the shipper.
+        client.release_milestone(&SHIPMENT_ID, &2);
+        let penalty = AMOUNT / 2 * 4 / 100;
+        assert_eq!(token.balance(&carrier), AMOUNT - penalty);
+        assert_eq!(token.balance(&shipper), penalty);
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.penalty, penalty);
+        assert_eq!(record.status, EscrowStatus::Released);
+        assert_solvent(&client, &token_addr);
+    }
+
+    #[test]
+    fn test_late_delivery_penalty_is_capped() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Completed,
+        );
+        deliver_late(&shipments, 48);
+
+        client.release_payment(&SHIPMENT_ID);
+
+        let penalty = AMOUNT * 15 / 100;
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&carrier),
+            AMOUNT - penalty
+        );
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).penalty, penalty);
+    }
+
+    #[test]
+    fn test_on_time_delivery_has_no_penalty() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Completed,
+        );
+        deliver_late(&shipments, 0);
+
+        client.settle_from_shipment(&SHIPMENT_ID);
+
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&carrier),
+            AMOUNT
+        );
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).penalty, 0);
+    }
+
+    #[test]
+    fn test_upgrade_requires_admin() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        assert_eq!(client.version(), CONTRACT_VERSION);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+
+        env.mock_auths(&[]);
+        assert!(client
+            .try_upgrade(&BytesN::from_array(&env, &[0; 32]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_upgrade_and_migrate_need_signers() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+
+        assert_eq!(
+            client.try_upgrade(&BytesN::from_array(&env, &[0; 32])),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+        assert_eq!(
+            client.try_migrate(&vec![&env, SHIPMENT_ID], &true),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+
+        env.as_contract(&client.address, || {
+            env.storage().instance().remove(&DataKey::SchemaVersion);
+        });
+        let id = client.propose_action(
+            &s1,
+            &0,
+            &EscrowAction::Migrate(vec![&env, SHIPMENT_ID], true),
+        );
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_schema_1_escrow_migrated_and_settles() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let other_token = Address::generate(&env);
+
+        // Rewind storage to the schema 1 layout: one token, lump-sum records.
+        env.as_contract(&client.address, || {
+            for shipment_id in [SHIPMENT_ID, SHIPMENT_ID + 1] {
+                let old = EscrowRecordV1 {
+                    shipment_id,
+                    shipper: shipper.clone(),
+                    carrier: carrier.clone(),
+                    amount: AMOUNT,
+                    status: EscrowStatus::Funded,
+                    funded_at: 0,
+                    settled_at: 0,
+                };
+                env.storage()
+                    .persistent()
+                    .set(&DataKey::Escrow(shipment_id), &old);
+            }
+            let storage = env.storage().instance();
+            storage.set(&DataKey::TokenContract, &token_addr);
+            storage.set(&DataKey::AllowedTokens, &vec![&env, other_token.clone()]);
+            storage.remove(&DataKey::SchemaVersion);
+        });
+        assert_eq!(client.schema_version(), 1);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).token, token_addr);
+
+        assert_eq!(client.migrate(&vec![&env, SHIPMENT_ID, 7], &true), 1);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+        assert_eq!(
+            client.get_allowed_tokens(),
+            vec![&env, other_token.clone(), token_addr.clone()]
+        );
+        // A record the batches missed still upgrades in its own token.
+        assert_eq!(client.get_escrow(&(SHIPMENT_ID + 1)).token, token_addr);
+
+        // Delisting the legacy token survives a later migrate call.
+        client.disallow_token(&token_addr);
+        assert_eq!(client.migrate(&vec![&env, SHIPMENT_ID + 1], &true), 1);
+        assert_eq!(client.get_allowed_tokens(), vec![&env, other_token]);
+
+        client.release_payment(&SHIPMENT_ID);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&carrier),
+            AMOUNT
+        );
+    }
+
+    fn approve(env: &Env, token_addr: &Address, client: &EscrowContractClient, from: &Address) {
+        TokenClient::new(env, token_addr).approve(
+            from,
+            &client.address,
+            &AMOUNT,
+            &(env.ledger().sequence() + 1000),
+        );
+    }
+
+    #[test]
+    fn test_incremental_funding_from_two_payers() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let consignee = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_addr).mint(&consignee, &AMOUNT);
+
+        client.create_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Pending
+        );
+        assert_eq!(
+            client.try_release_payment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
+
+        approve(&env, &token_addr, &client, &shipper);
+        approve(&env, &token_addr, &client, &consignee);
+        client.deposit(&shipper, &SHIPMENT_ID, &(AMOUNT * 3 / 5));
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Pending
+        );
+
+        client.deposit(&consignee, &SHIPMENT_ID, &(AMOUNT * 2 / 5));
+        assert_eq!(
+            escrow_events(&env, &client),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    topics(&env, "deposit"),
+                    DepositEvent {
+                        shipment_id: SHIPMENT_ID,
+                        payer: consignee.clone(),
+                        amount: AMOUNT * 2 / 5,
+                        deposited: AMOUNT,
+                    }
+                    .into_val(&env),
+                ),
+                (
+                    client.address.clone(),
+                    topics(&env, "funded"),
+                    FundedEvent {
+                        shipment_id: SHIPMENT_ID,
+                        round: 1,
+                        shipper: shipper.clone(),
+                        payer: shipper.clone(),
+                        carrier: carrier.clone(),
+                        token: token_addr.clone(),
+                        amount: AMOUNT,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.release_payment(&SHIPMENT_ID);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&carrier),
+            AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_price_renegotiation_top_up_and_overpayment() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+
+        // Price goes up: back to Pending until topped up.
+        client.adjust_amount(&SHIPMENT_ID, &(AMOUNT + AMOUNT / 2));
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Pending
+        );
+        approve(&env, &token_addr, &client, &shipper);
+        client.deposit(&shipper, &SHIPMENT_ID, &AMOUNT);
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Funded);
+        assert_eq!(record.deposited, AMOUNT * 2);
+
+        // The half overpaid goes back to the shipper.
+        assert_eq!(client.withdraw_excess(&shipper, &SHIPMENT_ID), AMOUNT / 2);
+        assert_eq!(
+            client.try_withdraw_excess(&shipper, &SHIPMENT_ID),
+            Err(Ok(EscrowError::NoExcess))
+        );
+
+        client.release_payment(&SHIPMENT_ID);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT + AMOUNT / 2);
+        assert_eq!(token.balance(&shipper), AMOUNT / 2);
+    }
+
+    #[test]
+    fn test_pending_deposits_withdrawable() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.create_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT);
+        approve(&env, &token_addr, &client, &shipper);
+        client.deposit(&shipper, &SHIPMENT_ID, &(AMOUNT / 4));
+
+        assert_eq!(client.withdraw_excess(&shipper, &SHIPMENT_ID), AMOUNT / 4);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&shipper),
+            AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_cancel_pending_returns_each_deposit() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let consignee = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_addr).mint(&consignee, &AMOUNT);
+        client.create_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT);
+        approve(&env, &token_addr, &client, &shipper);
+        approve(&env, &token_addr, &client, &consignee);
+        client.deposit(&shipper, &SHIPMENT_ID, &(AMOUNT / 4));
+        client.deposit(&consignee, &SHIPMENT_ID, &(AMOUNT / 2));
+
+        client.cancel_pending(&SHIPMENT_ID);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(token.balance(&consignee), AMOUNT);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Refunded
+        );
+        assert_eq!(
+            client.try_cancel_pending(&SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
+        assert_solvent(&client, &token_addr);
+        assert_eq!(client.get_liabilities(&token_addr), 0);
+
+        // Nothing was settled, so the shipment can be funded again at once.
+        client.create_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).round, 2);
+    }
+
+    #[test]
+    fn test_refund_and_excess_split_between_depositors() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let consignee = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_addr).mint(&consignee, &AMOUNT);
+        client.create_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &(AMOUNT / 2));
+        approve(&env, &token_addr, &client, &shipper);
+        approve(&env, &token_addr, &client, &consignee);
+        client.deposit(&shipper, &SHIPMENT_ID, &(AMOUNT * 3 / 5));
+        client.deposit(&consignee, &SHIPMENT_ID, &(AMOUNT * 2 / 5));
+
+        // Either depositor may return the overpayment; both get their share.
+        assert_eq!(
+            client.try_withdraw_excess(&Address::generate(&env), &SHIPMENT_ID),
+            Err(Ok(EscrowError::Unauthorized))
+        );
+        assert_eq!(client.withdraw_excess(&consignee, &SHIPMENT_ID), AMOUNT / 2);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT * 7 / 10);
+        assert_eq!(token.balance(&consignee), AMOUNT * 8 / 10);
+
+        client.refund_payment(&SHIPMENT_ID);
+        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(token.balance(&consignee), AMOUNT);
+        assert_solvent(&client, &token_addr);
+    }
+
+    #[test]
+    fn test_create_escrow_limited_to_linked_shipper() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        link_shipments(&env, &client, &shipper, None, ShipmentStatus::Created);
+        let squatter = Address::generate(&env);
+
+        assert_eq!(
+            client.try_create_escrow(&squatter, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT),
+            Err(Ok(EscrowError::PartyMismatch))
+        );
+        assert_eq!(
+            client.try_create_escrow(&shipper, &carrier, &7, &token_addr, &AMOUNT),
+            Err(Ok(EscrowError::ShipmentNotSettled))
+        );
+        client.create_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT);
+    }
+
+    fn fund_by_consignee(
+        env: &Env,
+        token_addr: &Address,
+        client: &EscrowContractClient,
+        shipper: &Address,
+        carrier: &Address,
+    ) -> Address {
+        let consignee = Address::generate(env);
+        StellarAssetClient::new(env, token_addr).mint(&consignee, &AMOUNT);
+        approve(env, token_addr, client, &consignee);
+        client.fund_escrow_for(
+            &consignee,
+            shipper,
+            carrier,
+            &SHIPMENT_ID,
+            token_addr,
+            &AMOUNT,
+        );
+        consignee
+    }
+
+    #[test]
+    fn test_consignee_pays_and_shipper_confirms() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(0);
+        let consignee = fund_by_consignee(&env, &token_addr, &client, &shipper, &carrier);
+
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.payer, consignee);
+        assert_eq!(record.shipper, shipper);
+        assert_eq!(record.status, EscrowStatus::Funded);
+
+        client.release_payment(&SHIPMENT_ID);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+        assert_eq!(token.balance(&consignee), 0);
+    }
+
+    #[test]
+    fn test_refunds_go_to_payer() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(0);
+        let consignee = fund_by_consignee(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.refund_payment(&SHIPMENT_ID);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&consignee), AMOUNT);
+        assert_eq!(token.balance(&shipper), 0);
+    }
+
+    #[test]
+    fn test_split_ruling_returns_remainder_to_payer() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(0);
+        let consignee = fund_by_consignee(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        client.resolve_dispute_split(
+            &SHIPMENT_ID,
+            &DisputeRuling {
+                carrier_bps: 6_000,
+                fee_bps: 0,
+                fee_recipient: None,
+                slash_bps: 0,
+            },
+        );
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT * 3 / 5);
+        assert_eq!(token.balance(&consignee), AMOUNT * 2 / 5);
+        assert_eq!(token.balance(&shipper), 0);
+    }
+
+    #[test]
+    fn test_disputed_escrow_cannot_be_funded_again() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+
+        assert_eq!(
+            client.try_fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT),
+            Err(Ok(EscrowError::AlreadyFunded))
+        );
+        assert_eq!(
+            client.try_reopen_escrow(&SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
+    }
+
+    #[test]
+    fn test_settled_escrow_reopened_into_new_round() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.release_payment(&SHIPMENT_ID);
+
+        assert_eq!(
+            client.try_fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT),
+            Err(Ok(EscrowError::SettlementFinal))
+        );
+
+        client.reopen_escrow(&SHIPMENT_ID);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.refund_payment(&SHIPMENT_ID);
+
+        let current = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(current.round, 2);
+        assert_eq!(current.status, EscrowStatus::Refunded);
+
+        let first = client.get_escrow_round(&SHIPMENT_ID, &1);
+        assert_eq!(first.round, 1);
+        assert_eq!(first.status, EscrowStatus::Released);
+
+        let history = client.get_escrow_history(&SHIPMENT_ID);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.get(0).unwrap().status, EscrowStatus::Released);
+        assert_eq!(history.get(1).unwrap().status, EscrowStatus::Refunded);
+        assert!(matches!(
+            client.try_get_escrow_round(&SHIPMENT_ID, &3),
+            Err(Ok(EscrowError::NotFound))
+        ));
+
+        // Each reopening allows exactly one more round.
+        assert_eq!(
+            client.try_fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT),
+            Err(Ok(EscrowError::SettlementFinal))
+        );
+    }
+
+    #[test]
+    fn test_reopen_closes_pending_round_unless_paused() {
+        let (env, admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.create_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT);
+        approve(&env, &token_addr, &client, &shipper);
+        client.deposit(&shipper, &SHIPMENT_ID, &(AMOUNT / 4));
+
+        client.pause(&admin, &PauseScope::Settlement);
+        assert_eq!(
+            client.try_reopen_escrow(&SHIPMENT_ID),
+            Err(Ok(EscrowError::Paused))
+        );
+        client.unpause(&PauseScope::Settlement);
+
+        client.reopen_escrow(&SHIPMENT_ID);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&shipper),
+            AMOUNT
+        );
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Refunded
+        );
+        assert_solvent(&client, &token_addr);
+
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).round, 2);
+    }
+
+    /// Fund `count` escrows with consecutive ids starting at `SHIPMENT_ID`.
+    fn fund_many(
+        env: &Env,
+        token_addr: &Address,
+        client: &EscrowContractClient,
+        shipper: &Address,
+        carrier: &Address,
+        count: u64,
+    ) -> Vec<u64> {
+        TokenClient::new(env, token_addr).approve(
+            shipper,
+            &client.address,
+            &(AMOUNT * count as i128),
+            &(env.ledger().sequence() + 1000),
+        );
+        let mut ids = Vec::new(env);
+        for id in SHIPMENT_ID..SHIPMENT_ID + count {
+            client.fund_escrow(shipper, carrier, &id, token_addr, &AMOUNT);
+            ids.push_back(id);
+        }
+        ids
+    }
+
+    #[test]
+    fn test_release_batch_reports_each_shipment() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 3);
+        let mut ids = fund_many(&env, &token_addr, &client, &shipper, &carrier, 3);
+        client.raise_dispute(&carrier, &(SHIPMENT_ID + 1));
+        ids.push_back(999);
+
+        let results = client.release_batch(&ids);
+        let expected = [
+            BatchOutcome::Settled,
+            BatchOutcome::Failed(EscrowError::InvalidStatus as u32),
+            BatchOutcome::Settled,
+            BatchOutcome::Failed(EscrowError::NotFound as u32),
+        ];
+        assert_eq!(results.len(), 4);
+        for (result, outcome) in results.iter().zip(expected) {
+            assert_eq!(result.outcome, outcome);
+        }
+        assert_eq!(results.get(3).unwrap().shipment_id, 999);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT * 2);
+        assert_eq!(
+            client.get_escrow(&(SHIPMENT_ID + 1)).status,
+            EscrowStatus::Disputed
+        );
+    }
+
+    #[test]
+    fn test_refund_batch_schedules_under_delay() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+        let ids = fund_many(&env, &token_addr, &client, &shipper, &carrier, 2);
+        client.set_settlement_delay(&DELAY);
+
+        let results = client.refund_batch(&ids);
+        assert!(results.iter().all(|r| r.outcome == BatchOutcome::Scheduled));
+    }
+
+    #[test]
+    fn test_batch_size_bounded() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        assert_eq!(
+            client.try_release_batch(&Vec::new(&env)),
+            Err(Ok(EscrowError::InvalidBatch))
+        );
+        let mut ids = Vec::new(&env);
+        for id in 0..MAX_BATCH_SIZE as u64 + 1 {
+            ids.push_back(id);
+        }
+        assert_eq!(
+            client.try_refund_batch(&ids),
+            Err(Ok(EscrowError::InvalidBatch))
+        );
+    }
+
+    #[test]
+    fn test_full_batch_fits_resource_budget() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * MAX_BATCH_SIZE as i128);
+        let treasury = Address::generate(&env);
+        client.set_commission(&250, &treasury, &false);
+        let ids = fund_many(
+            &env,
+            &token_addr,
+            &client,
+            &shipper,
+            &carrier,
+            MAX_BATCH_SIZE as u64,
+        );
+
+        // Metering resets per invocation; the default budget is the network's
+        // per-transaction limit, so exceeding it would fail the call outright.
+        let results = client.release_batch(&ids);
+        assert!(results.iter().all(|r| r.outcome == BatchOutcome::Settled));
+
+        let budget = env.cost_estimate().budget();
+        assert!(budget.cpu_instruction_cost() < 100_000_000);
+        assert!(budget.memory_bytes_cost() < 40 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_party_index_pages_newest_first() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 4);
+        fund_many(&env, &token_addr, &client, &shipper, &carrier, 3);
+        let other = Address::generate(&env);
+        approve(&env, &token_addr, &client, &shipper);
+        client.fund_escrow(&shipper, &other, &99, &token_addr, &AMOUNT);
+
+        let first = client.get_escrows_by_party(&PartyRole::Shipper, &shipper, &None, &3);
+        assert_eq!(first.records.len(), 3);
+        assert_eq!(first.records.get(0).unwrap().shipment_id, 99);
+        assert_eq!(first.records.get(2).unwrap().shipment_id, SHIPMENT_ID + 1);
+        assert_eq!(first.next_cursor, Some(1));
+
+        let second =
+            client.get_escrows_by_party(&PartyRole::Shipper, &shipper, &first.next_cursor, &3);
+        assert_eq!(second.records.len(), 1);
+        assert_eq!(second.records.get(0).unwrap().shipment_id, SHIPMENT_ID);
+        assert_eq!(second.next_cursor, None);
+
+        let carried = client.get_escrows_by_party(&PartyRole::Carrier, &other, &None, &0);
+        assert_eq!(carried.records.len(), 1);
+        assert_eq!(carried.records.get(0).unwrap().shipment_id, 99);
+    }
+
+    #[test]
+    fn test_status_index_skips_stale_entries() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 3);
+        fund_many(&env, &token_addr, &client, &shipper, &carrier, 3);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        client.release_payment(&(SHIPMENT_ID + 1));
+
+        let funded = client.get_escrows_by_status(&StatusKind::Funded, &None, &0);
+        assert_eq!(funded.records.len(), 1);
+        assert_eq!(funded.records.get(0).unwrap().shipment_id, SHIPMENT_ID + 2);
+
+        let disputed = client.get_escrows_by_status(&StatusKind::Disputed, &None, &0);
+        assert_eq!(disputed.records.len(), 1);
+        assert_eq!(disputed.records.get(0).unwrap().shipment_id, SHIPMENT_ID);
+
+        assert_eq!(client.count_by_status(&StatusKind::Funded), 1);
+        assert_eq!(client.count_by_status(&StatusKind::Disputed), 1);
+        assert_eq!(client.count_by_status(&StatusKind::Released), 1);
+    }
+
+    #[test]
+    fn test_locked_totals_follow_settlement() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+        fund_many(&env, &token_addr, &client, &shipper, &carrier, 2);
+        let locked =
+            |role: PartyRole, party: &Address| client.get_locked(&role, party, &token_addr);
+        assert_eq!(locked(PartyRole::Shipper, &shipper), AMOUNT * 2);
+        assert_eq!(locked(PartyRole::Carrier, &carrier), AMOUNT * 2);
+
+        client.raise_dispute(&carrier, &SHIPMENT_ID);
+        assert_eq!(locked(PartyRole::Carrier, &carrier), AMOUNT * 2);
+
+        client.release_payment(&(SHIPMENT_ID + 1));
+        client.resolve_dispute(&SHIPMENT_ID, &false);
+        assert_eq!(locked(PartyRole::Shipper, &shipper), 0);
+        assert_eq!(locked(PartyRole::Carrier, &carrier), 0);
+    }
+
+    /// The contract holds exactly what it owes escrows.
+    fn assert_solvent(client: &EscrowContractClient, token_addr: &Address) {
+        let reconciliation = client.reconcile(token_addr);
+        assert_eq!(reconciliation.surplus, 0);
+        assert_eq!(reconciliation.liabilities, reconciliation.balance);
+    }
+
+    #[test]
+    fn test_liabilities_follow_release_refund_and_cancel() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 3);
+        client.set_commission(&250, &Address::generate(&env), &true);
+        client.set_cancellation_fees(&CancellationFees {
+            pending: 0,
+            accepted: 1_000,
+            in_transit: 2_500,
+        });
+        fund_many(&env, &token_addr, &client, &shipper, &carrier, 3);
+        assert_eq!(client.get_liabilities(&token_addr), AMOUNT * 3);
+        assert_solvent(&client, &token_addr);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::InTransit,
+        );
+        for id in [SHIPMENT_ID + 1, SHIPMENT_ID + 2] {
+            let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+            shipment.id = id;
+            shipments.put(&shipment);
+        }
+
+        client.release_payment(&SHIPMENT_ID);
+        assert_solvent(&client, &token_addr);
+        client.refund_payment(&(SHIPMENT_ID + 1));
+        assert_solvent(&client, &token_addr);
+        client.cancel_escrow(&(SHIPMENT_ID + 2));
+        assert_solvent(&client, &token_addr);
+        assert_eq!(client.get_liabilities(&token_addr), 0);
+    }
+
+    #[test]
+    fn test_liabilities_follow_milestones_and_rulings() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_commission(&250, &Address::generate(&env), &false);
+        fund_milestones(&env, &token_addr, &client, &shipper, &carrier);
+
+        client.release_milestone(&SHIPMENT_ID, &0);
+        assert_eq!(client.get_liabilities(&token_addr), AMOUNT * 4 / 5);
+        assert_solvent(&client, &token_addr);
+
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        let treasury = Address::generate(&env);
+        client.set_arbitration_treasury(&treasury);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        let ruling = DisputeRuling {
+            carrier_bps: 6_000,
+            fee_bps: 500,
+            fee_recipient: Some(treasury),
+            slash_bps: 0,
+        };
+        let id = client.propose_action(&s1, &SHIPMENT_ID, &EscrowAction::ResolveSplit(ruling));
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+        assert_solvent(&client, &token_addr);
+        assert_eq!(client.get_liabilities(&token_addr), 0);
+    }
+
+    #[test]
+    fn test_liabilities_follow_deposits_and_timelock() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
+        client.create_escrow(&shipper, &carrier, &SHIPMENT_ID, &token_addr, &AMOUNT);
+        approve(&env, &token_addr, &client, &shipper);
+        client.deposit(&shipper, &SHIPMENT_ID, &(AMOUNT / 2));
+        assert_eq!(client.get_liabilities(&token_addr), AMOUNT / 2);
+        assert_solvent(&client, &token_addr);
+
+        approve(&env, &token_addr, &client, &shipper);
+        client.deposit(&shipper, &SHIPMENT_ID, &AMOUNT);
+        client.withdraw_excess(&shipper, &SHIPMENT_ID);
+        assert_eq!(client.get_liabilities(&token_addr), AMOUNT);
+        assert_solvent(&client, &token_addr);
+
+        client.set_settlement_delay(&DELAY);
+        client.release_payment(&SHIPMENT_ID);
+        assert_solvent(&client, &token_addr);
+        env.ledger().with_mut(|l| l.timestamp += DELAY);
+        client.execute_settlement(&SHIPMENT_ID);
+        assert_solvent(&client, &token_addr);
+        assert_eq!(client.get_liabilities(&token_addr), 0);
+    }
+
+    #[test]
+    fn test_liabilities_follow_late_penalty() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Completed,
+        );
+        deliver_late(&shipments, 4);
+
+        client.settle_from_shipment(&SHIPMENT_ID);
+        assert_solvent(&client, &token_addr);
+        assert_eq!(client.get_liabilities(&token_addr), 0);
+    }
+
+    #[test]
+    fn test_sweep_surplus_only_moves_excess() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let stray = AMOUNT / 10;
+        StellarAssetClient::new(&env, &token_addr).mint(&client.address, &stray);
+
+        let reconciliation = client.reconcile(&token_addr);
+        assert_eq!(reconciliation.balance, AMOUNT + stray);
+        assert_eq!(reconciliation.surplus, stray);
+
+        let treasury = Address::generate(&env);
+        assert_eq!(
+            client.try_sweep_surplus(&token_addr, &treasury, &(stray + 1)),
+            Err(Ok(EscrowError::InsufficientBalance))
+        );
+        client.sweep_surplus(&token_addr, &treasury, &stray);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&treasury),
+            stray
+        );
+        assert_solvent(&client, &token_addr);
+
+        client.release_payment(&SHIPMENT_ID);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&carrier),
+            AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_sweep_goes_through_proposals_once_signers_set() {
+        let (env, _, _, _, token_addr, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        StellarAssetClient::new(&env, &token_addr).mint(&client.address, &AMOUNT);
+        let treasury = Address::generate(&env);
+
+        assert_eq!(
+            client.try_sweep_surplus(&token_addr, &treasury, &AMOUNT),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+        let action = EscrowAction::SweepSurplus(token_addr.clone(), treasury.clone(), AMOUNT);
+        let id = client.propose_action(&s1, &0, &action);
+        assert_eq!(
+            client.try_execute_proposal(&id),
+            Err(Ok(EscrowError::ThresholdNotMet))
+        );
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&treasury),
+            AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_sweep_waits_for_migration() {
+        let (env, _, _, _, token_addr, client) = setup(AMOUNT);
+        env.as_contract(&client.address, || {
+            env.storage().instance().remove(&DataKey::SchemaVersion);
+        });
+        assert_eq!(
+            client.try_sweep_surplus(&token_addr, &Address::generate(&env), &1),
+            Err(Ok(EscrowError::MigrationPending))
+        );
+    }
+
+    const BOND: i128 = AMOUNT / 10;
+
+    /// Mint `amount` to the carrier and let the escrow pull it.
+    fn bond_funds(
+        env: &Env,
+        token_addr: &Address,
+        client: &EscrowContractClient,
+        carrier: &Address,
+        amount: i128,
+    ) {
+        StellarAssetClient::new(env, token_addr).mint(carrier, &amount);
+        approve(env, token_addr, client, carrier);
+    }
+
+    #[test]
+    fn test_bond_freed_on_release_and_withdrawn_after_cooldown() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        bond_funds(&env, &token_addr, &client, &carrier, BOND);
+
+        client.post_bond(&carrier, &SHIPMENT_ID, &BOND);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).bond, BOND);
+        assert_eq!(client.get_bond(&carrier, &token_addr).committed, BOND);
+        assert_eq!(client.get_liabilities(&token_addr), AMOUNT + BOND);
+        assert_eq!(
+            client.try_request_bond_withdrawal(&carrier, &token_addr, &BOND),
+            Err(Ok(EscrowError::InsufficientBond))
+        );
+
+        client.release_payment(&SHIPMENT_ID);
+        let account = client.get_bond(&carrier, &token_addr);
+        assert_eq!(account.balance, BOND);
+        assert_eq!(account.committed, 0);
+
+        client.request_bond_withdrawal(&carrier, &token_addr, &BOND);
+        assert_eq!(
+            client.try_withdraw_bond(&carrier, &token_addr),
+            Err(Ok(EscrowError::BondLocked))
+        );
+        env.ledger()
+            .with_mut(|l| l.timestamp += DEFAULT_BOND_COOLDOWN);
+        assert_eq!(client.withdraw_bond(&carrier, &token_addr), BOND);
+        assert_eq!(
+            TokenClient::new(&env, &token_addr).balance(&carrier),
+            AMOUNT + BOND
+        );
+        assert_eq!(
+            client.try_withdraw_bond(&carrier, &token_addr),
+            Err(Ok(EscrowError::NoWithdrawalPending))
+        );
+        assert_solvent(&client, &token_addr);
+    }
+
+    #[test]
+    fn test_bond_withdrawal_request_frozen_while_paused() {
+        let (env, admin, _, carrier, token_addr, client) = setup(AMOUNT);
+        bond_funds(&env, &token_addr, &client, &carrier, BOND);
+        client.deposit_bond(&carrier, &token_addr, &BOND);
+
+        client.pause(&admin, &PauseScope::Settlement);
+        assert_eq!(
+            client.try_request_bond_withdrawal(&carrier, &token_addr, &BOND),
+            Err(Ok(EscrowError::Paused))
+        );
+        client.unpause(&PauseScope::Settlement);
+        client.request_bond_withdrawal(&carrier, &token_addr, &BOND);
+    }
+
+    #[test]
+    fn test_ruling_against_carrier_slashes_standing_bond() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        bond_funds(&env, &token_addr, &client, &carrier, BOND * 2);
+        client.deposit_bond(&carrier, &token_addr, &(BOND * 2));
+
+        // Covered by the standing balance: nothing more leaves the wallet.
+        client.post_bond(&carrier, &SHIPMENT_ID, &BOND);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), 0);
+
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        client.resolve_dispute_split(
+            &SHIPMENT_ID,
+            &DisputeRuling {
+                carrier_bps: 0,
+                fee_bps: 0,
+                fee_recipient: None,
+                slash_bps: 5_000,
+            },
+        );
+
+        assert_eq!(token.balance(&shipper), AMOUNT + BOND / 2);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).bond_slashed, BOND / 2);
+        let account = client.get_bond(&carrier, &token_addr);
+        assert_eq!(account.balance, BOND * 2 - BOND / 2);
+        assert_eq!(account.committed, 0);
+        assert_solvent(&client, &token_addr);
+    }
+
+    #[test]
+    fn test_slash_needs_bond_and_adverse_ruling() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+
+        let ruling = |carrier_bps: u32| DisputeRuling {
+            carrier_bps,
+            fee_bps: 0,
+            fee_recipient: None,
+            slash_bps: 2_000,
+        };
+        assert_eq!(
+            client.try_resolve_dispute_split(&SHIPMENT_ID, &ruling(4_000)),
+            Err(Ok(EscrowError::NoBondPosted))
+        );
+        assert_eq!(
+            client.try_resolve_dispute_split(&SHIPMENT_ID, &ruling(10_000)),
+            Err(Ok(EscrowError::InvalidRuling))
+        );
+        assert_eq!(
+            client.try_post_bond(&shipper, &SHIPMENT_ID, &BOND),
+            Err(Ok(EscrowError::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_assigned_payout_goes_to_financier() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let financier = Address::generate(&env);
+
+        client.assign_payout(&carrier, &SHIPMENT_ID, &financier);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).assignees,
+            vec![&env, financier.clone()]
+        );
+
+        client.release_payment(&SHIPMENT_ID);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&financier), AMOUNT);
+        assert_eq!(token.balance(&carrier), 0);
+        assert_eq!(
+            client.try_revoke_assignment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
+    }
+
+    #[test]
+    fn test_assignment_chains_and_revokes_to_previous_holder() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let first = Address::generate(&env);
+        let second = Address::generate(&env);
+
+        client.assign_payout(&carrier, &SHIPMENT_ID, &first);
+        assert_eq!(
+            client.try_assign_payout(&carrier, &SHIPMENT_ID, &second),
+            Err(Ok(EscrowError::Unauthorized))
+        );
+        client.assign_payout(&first, &SHIPMENT_ID, &second);
+
+        client.revoke_assignment(&SHIPMENT_ID);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).assignees,
+            vec![&env, first.clone()]
+        );
+
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        client.resolve_dispute(&SHIPMENT_ID, &true);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&first), AMOUNT);
+        assert_eq!(token.balance(&second), 0);
+        assert_eq!(token.balance(&carrier), 0);
+    }
+
+    #[test]
+    fn test_revoke_without_assignment_rejected() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        assert_eq!(
+            client.try_revoke_assignment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::NotAssigned))
+        );
+    }
+
+    #[test]
+    fn test_assignment_frozen_while_paused() {
+        let (env, admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        let financier = Address::generate(&env);
+        client.assign_payout(&carrier, &SHIPMENT_ID, &financier);
+
+        client.pause(&admin, &PauseScope::Settlement);
+        assert_eq!(
+            client.try_assign_payout(&financier, &SHIPMENT_ID, &Address::generate(&env)),
+            Err(Ok(EscrowError::Paused))
+        );
+        assert_eq!(
+            client.try_revoke_assignment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::Paused))
+        );
+    }
+
+    const TRANSIT: u64 = 10 * 3_600;
+    const PICKUP_AT: u64 = 1_000;
+
+    /// Fund a streaming escrow and put the linked shipment in transit.
+    fn fund_streaming(
+        env: &Env,
+        token_addr: &Address,
+        client: &EscrowContractClient,
+        shipper: &Address,
+        carrier: &Address,
+    ) -> MockShipmentsClient<'static> {
+        approve(env, token_addr, client, shipper);
+        client.fund_escrow_streaming(
+            shipper,
+            carrier,
+            &SHIPMENT_ID,
+            token_addr,
+            &AMOUNT,
+            &TRANSIT,
+        );
+        let shipments = link_shipments(
+            env,
+            client,
+            shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::InTransit,
+        );
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.updated_at = PICKUP_AT;
+        shipment.picked_up_at = PICKUP_AT;
+        shipments.put(&shipment);
+        env.ledger().with_mut(|l| l.timestamp = PICKUP_AT);
+        shipments
+    }
+
+    #[test]
+    fn test_stream_vests_linearly_until_completion() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_streaming(&env, &token_addr, &client, &shipper, &carrier);
+        assert_eq!(
+            client.try_withdraw_vested(&SHIPMENT_ID),
+            Err(Ok(EscrowError::NothingVested))
+        );
+        client.start_stream(&SHIPMENT_ID);
 
-        if record.status != EscrowStatus::Disputed {
-            return Err(EscrowError::InvalidStatus);
-        }
+        env.ledger().with_mut(|l| l.timestamp += TRANSIT / 4);
+        assert_eq!(client.withdraw_vested(&SHIPMENT_ID), AMOUNT / 4);
+        assert_eq!(
+            client.try_withdraw_vested(&SHIPMENT_ID),
+            Err(Ok(EscrowError::NothingVested))
+        );
 
-        let token_addr: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::TokenContract)
-            .unwrap();
-        let token = token::Client::new(&env, &token_addr);
+        client.release_payment(&SHIPMENT_ID);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT);
+        assert_eq!(token.balance(&shipper), 0);
+    }
 
-        let recipient = if release_to_carrier {
-            record.carrier.clone()
-        } else {
-            record.shipper.clone()
-        };
+    #[test]
+    fn test_dispute_freezes_vesting() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_streaming(&env, &token_addr, &client, &shipper, &carrier);
+        client.start_stream(&SHIPMENT_ID);
 
-        token.transfer(&env.current_contract_address(), &recipient, &record.amount);
+        env.ledger().with_mut(|l| l.timestamp += TRANSIT / 2);
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        env.ledger().with_mut(|l| l.timestamp += TRANSIT);
+        assert_eq!(client.get_vested(&SHIPMENT_ID), AMOUNT / 2);
+        assert_eq!(
+            client.try_withdraw_vested(&SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
 
-        record.status = if release_to_carrier {
-            EscrowStatus::Released
-        } else {
-            EscrowStatus::Refunded
-        };
-        record.settled_at = env.ledger().timestamp();
-        Self::store(&env, &record);
-        Ok(())
+        // A refund ruling only covers what had not vested when it froze.
+        client.resolve_dispute(&SHIPMENT_ID, &false);
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT / 2);
+        assert_eq!(token.balance(&shipper), AMOUNT / 2);
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.released_amount, AMOUNT / 2);
+        assert_eq!(
+            record.resolution,
+            Resolution::Ruled(
+                DisputeRuling {
+                    carrier_bps: 0,
+                    fee_bps: 0,
+                    fee_recipient: None,
+                    slash_bps: 0,
+                },
+                SplitAmounts {
+                    carrier: 0,
+                    shipper: AMOUNT / 2,
+                    fee: 0,
+                }
+            )
+        );
+        assert_solvent(&client, &token_addr);
     }
 
-    // ── Queries ───────────────────────────────────────────────────────────
+    #[test]
+    fn test_cancelled_stream_refunds_unvested() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        client.set_cancellation_fees(&CancellationFees {
+            pending: 0,
+            accepted: 1_000,
+            in_transit: 2_500,
+        });
+        fund_streaming(&env, &token_addr, &client, &shipper, &carrier);
+        client.start_stream(&SHIPMENT_ID);
 
-    pub fn get_escrow(env: Env, shipment_id: u64) -> Result<EscrowRecord, EscrowError> {
-        Self::load(&env, shipment_id)
-    }
+        env.ledger().with_mut(|l| l.timestamp += TRANSIT * 2 / 5);
+        client.withdraw_vested(&SHIPMENT_ID);
+        env.ledger().with_mut(|l| l.timestamp += TRANSIT / 5);
+        client.cancel_escrow(&SHIPMENT_ID);
 
-    /// Read the configured admin address.
-    ///
-    /// Lets external callers (e.g. the backend's Soroban integration layer)
-    /// verify their loaded signing keypair actually matches this contract's
-    /// admin at startup, instead of discovering a mismatch on first real
-    /// admin-gated call.
-    pub fn get_admin(env: Env) -> Result<Address, EscrowError> {
-        env.storage()
-            .instance()
-            .get(&DataKey::Admin)
-            .ok_or(EscrowError::NotInitialized)
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT * 3 / 5);
+        assert_eq!(token.balance(&shipper), AMOUNT * 2 / 5);
+        assert_solvent(&client, &token_addr);
     }
 
-    pub fn get_balance(env: Env) -> i128 {
-        let token_addr: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::TokenContract)
-            .unwrap_or_else(|| panic!());
-        let token = token::Client::new(&env, &token_addr);
-        token.balance(&env.current_contract_address())
-    }
+    #[test]
+    fn test_refunded_stream_pays_vested_share() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_streaming(&env, &token_addr, &client, &shipper, &carrier);
+        client.start_stream(&SHIPMENT_ID);
 
-    // ── Helpers ───────────────────────────────────────────────────────────
+        // No fee table: the refund still leaves the carrier what vested.
+        env.ledger().with_mut(|l| l.timestamp += TRANSIT * 3 / 5);
+        client.refund_payment(&SHIPMENT_ID);
 
-    fn load(env: &Env, shipment_id: u64) -> Result<EscrowRecord, EscrowError> {
-        env.storage()
-            .persistent()
-            .get(&DataKey::Escrow(shipment_id))
-            .ok_or(EscrowError::NotFound)
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), AMOUNT * 3 / 5);
+        assert_eq!(token.balance(&shipper), AMOUNT * 2 / 5);
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Refunded);
+        assert_eq!(record.released_amount, AMOUNT * 3 / 5);
+        assert_solvent(&client, &token_addr);
     }
 
-    fn store(env: &Env, record: &EscrowRecord) {
-        env.storage()
-            .persistent()
-            .set(&DataKey::Escrow(record.shipment_id), record);
-        env.storage().persistent().extend_ttl(
-            &DataKey::Escrow(record.shipment_id),
-            TTL_LEDGERS,
-            TTL_LEDGERS,
+    #[test]
+    fn test_stream_needs_shipment_in_transit() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let shipments = fund_streaming(&env, &token_addr, &client, &shipper, &carrier);
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::Accepted;
+        shipment.picked_up_at = 0;
+        shipments.put(&shipment);
+
+        assert_eq!(
+            client.try_start_stream(&SHIPMENT_ID),
+            Err(Ok(EscrowError::StreamNotStarted))
+        );
+        assert_eq!(
+            client.try_withdraw_vested(&SHIPMENT_ID),
+            Err(Ok(EscrowError::StreamNotStarted))
         );
     }
-}
 
-// ── Tests ─────────────────────────────────────────────────────────────────────
+    #[test]
+    fn test_stream_vests_from_pickup_without_start_stream() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let shipments = fund_streaming(&env, &token_addr, &client, &shipper, &carrier);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use soroban_sdk::{
-        testutils::Address as _,
-        token::{Client as TokenClient, StellarAssetClient},
-        Env,
-    };
+        // Nobody called `start_stream` before the cargo was delivered.
+        env.ledger().with_mut(|l| l.timestamp += TRANSIT / 2);
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::Delivered;
+        shipment.updated_at = env.ledger().timestamp();
+        shipments.put(&shipment);
 
-    /// Deploy a test SAC token, mint `amount` to `recipient`, return token address.
-    fn create_token(env: &Env, admin: &Address, recipient: &Address, amount: i128) -> Address {
-        let token_address = env
-            .register_stellar_asset_contract_v2(admin.clone())
-            .address();
-        let sac = StellarAssetClient::new(env, &token_address);
-        sac.mint(recipient, &amount);
-        token_address
+        assert_eq!(client.get_vested(&SHIPMENT_ID), AMOUNT / 2);
+        assert_eq!(client.withdraw_vested(&SHIPMENT_ID), AMOUNT / 2);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).stream.started_at,
+            Some(PICKUP_AT)
+        );
+        assert_eq!(
+            client.try_start_stream(&SHIPMENT_ID),
+            Err(Ok(EscrowError::InvalidStatus))
+        );
     }
 
-    fn setup(
-        shipper_balance: i128,
-    ) -> (
-        Env,
-        Address, // admin
-        Address, // shipper
-        Address, // carrier
-        Address, // token
-        EscrowContractClient<'static>,
-    ) {
-        let env = Env::default();
-        env.mock_all_auths();
+    #[test]
+    fn test_stream_start_frozen_while_paused() {
+        let (env, admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund_streaming(&env, &token_addr, &client, &shipper, &carrier);
 
-        let admin = Address::generate(&env);
-        let shipper = Address::generate(&env);
-        let carrier = Address::generate(&env);
+        client.pause(&admin, &PauseScope::Settlement);
+        assert_eq!(
+            client.try_start_stream(&SHIPMENT_ID),
+            Err(Ok(EscrowError::Paused))
+        );
+        client.unpause(&PauseScope::Settlement);
+        client.start_stream(&SHIPMENT_ID);
+    }
 
-        let token_addr = create_token(&env, &admin, &shipper, shipper_balance);
+    #[test]
+    fn test_lump_sum_escrow_does_not_stream() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        fund(&env, &token_addr, &client, &shipper, &carrier);
+        assert_eq!(
+            client.try_withdraw_vested(&SHIPMENT_ID),
+            Err(Ok(EscrowError::NotStreaming))
+        );
+    }
 
-        let contract_id = env.register(EscrowContract {}, ());
-        let client = EscrowContractClient::new(&env, &contract_id);
-        client.initialize(&admin, &token_addr);
+    const FIRST_LEG: i128 = AMOUNT * 3 / 5;
 
-        (env, admin, shipper, carrier, token_addr, client)
+    fn leg(carrier: &Address, price: i128, status: ShipmentStatus) -> Leg {
+        Leg {
+            carrier: Some(carrier.clone()),
+            price,
+            status,
+            delivered_at: 0,
+        }
     }
 
-    const AMOUNT: i128 = 500_000_000; // 50 XLM in stroops (7 decimals)
-    const SHIPMENT_ID: u64 = 42;
-
-    fn fund(
+    /// Fund a two-leg escrow: `carrier` has delivered the first leg, which
+    /// the shipper confirmed, and `second` is carrying the rest.
+    fn fund_multileg(
         env: &Env,
         token_addr: &Address,
         client: &EscrowContractClient,
         shipper: &Address,
         carrier: &Address,
-    ) {
-        // Approve the escrow contract to pull AMOUNT from the shipper before funding.
-        let token = TokenClient::new(env, token_addr);
-        token.approve(
+        second: &Address,
+    ) -> MockShipmentsClient<'static> {
+        let shipments = link_shipments(
+            env,
+            client,
             shipper,
-            &client.address,
-            &AMOUNT,
-            &(env.ledger().sequence() + 1000),
+            Some(carrier.clone()),
+            ShipmentStatus::InTransit,
+        );
+        shipments.put_legs(
+            &SHIPMENT_ID,
+            &vec![
+                env,
+                leg(carrier, FIRST_LEG, ShipmentStatus::Completed),
+                leg(second, AMOUNT - FIRST_LEG, ShipmentStatus::InTransit),
+            ],
         );
-        client.fund_escrow(shipper, carrier, &SHIPMENT_ID, &AMOUNT);
+        approve(env, token_addr, client, shipper);
+        client.fund_escrow_multileg(shipper, carrier, &SHIPMENT_ID, token_addr);
+        shipments
     }
 
     #[test]
-    fn test_fund_and_release() {
-        let (env, _admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
-
-        fund(&env, &token_addr, &client, &shipper, &carrier);
-
-        let record = client.get_escrow(&SHIPMENT_ID);
-        assert_eq!(record.status, EscrowStatus::Funded);
-        assert_eq!(record.amount, AMOUNT);
+    fn test_multileg_escrow_pays_each_leg_carrier() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        let shipments = fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).amount, AMOUNT);
+        assert_eq!(
+            client.try_release_payment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::PaidPerLeg))
+        );
+        assert_eq!(
+            client.try_release_leg(&SHIPMENT_ID, &1),
+            Err(Ok(EscrowError::LegNotConfirmed))
+        );
 
-        // Check contract holds the tokens
+        client.release_leg(&SHIPMENT_ID, &0);
         let token = TokenClient::new(&env, &token_addr);
-        assert_eq!(token.balance(&client.address), AMOUNT);
+        assert_eq!(token.balance(&carrier), FIRST_LEG);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).status, EscrowStatus::Funded);
+        assert_eq!(
+            client.try_release_leg(&SHIPMENT_ID, &0),
+            Err(Ok(EscrowError::LegAlreadyPaid))
+        );
+        assert_solvent(&client, &token_addr);
 
-        // Release to carrier
-        client.release_payment(&SHIPMENT_ID);
+        let mut legs = shipments.get_legs(&SHIPMENT_ID);
+        legs.set(
+            1,
+            leg(&second, AMOUNT - FIRST_LEG, ShipmentStatus::Completed),
+        );
+        shipments.put_legs(&SHIPMENT_ID, &legs);
+        client.release_leg(&SHIPMENT_ID, &1);
 
+        assert_eq!(token.balance(&second), AMOUNT - FIRST_LEG);
         let record = client.get_escrow(&SHIPMENT_ID);
         assert_eq!(record.status, EscrowStatus::Released);
-        assert_eq!(token.balance(&carrier), AMOUNT);
-        assert_eq!(token.balance(&client.address), 0);
+        assert_eq!(record.released_amount, AMOUNT);
+        assert_eq!(record.legs.get(1).unwrap().paid_to, Some(second));
+        assert_solvent(&client, &token_addr);
     }
 
     #[test]
-    fn test_fund_and_refund() {
-        let (env, _admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+    fn test_late_penalty_taken_from_final_leg() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        let shipments = fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
+        client.release_leg(&SHIPMENT_ID, &0);
 
-        fund(&env, &token_addr, &client, &shipper, &carrier);
+        deliver_late(&shipments, 4);
+        let mut legs = shipments.get_legs(&SHIPMENT_ID);
+        legs.set(
+            1,
+            leg(&second, AMOUNT - FIRST_LEG, ShipmentStatus::Completed),
+        );
+        shipments.put_legs(&SHIPMENT_ID, &legs);
+        client.release_leg(&SHIPMENT_ID, &1);
 
-        client.refund_payment(&SHIPMENT_ID);
+        // 4% of the final leg goes back to the shipper; the first leg is untouched.
+        let penalty = (AMOUNT - FIRST_LEG) * 4 / 100;
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), FIRST_LEG);
+        assert_eq!(token.balance(&second), AMOUNT - FIRST_LEG - penalty);
+        assert_eq!(token.balance(&shipper), penalty);
+        assert_eq!(client.get_escrow(&SHIPMENT_ID).penalty, penalty);
+        assert_solvent(&client, &token_addr);
+    }
 
-        let record = client.get_escrow(&SHIPMENT_ID);
-        assert_eq!(record.status, EscrowStatus::Refunded);
+    #[test]
+    fn test_ruled_multileg_shipment_settles_unpaid_legs() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        let shipments = fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
+
+        // The shipment contract ruled the dispute Completed; the second leg
+        // was never confirmed.
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::Completed;
+        shipments.put(&shipment);
+        client.settle_from_shipment(&SHIPMENT_ID);
 
         let token = TokenClient::new(&env, &token_addr);
-        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(token.balance(&carrier), FIRST_LEG);
+        assert_eq!(token.balance(&second), AMOUNT - FIRST_LEG);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).status,
+            EscrowStatus::Released
+        );
+        assert_solvent(&client, &token_addr);
     }
 
     #[test]
-    fn test_dispute_resolved_to_carrier() {
-        let (env, _admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+    fn test_confirmed_multileg_shipment_pays_per_leg() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        let shipments = fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
 
-        fund(&env, &token_addr, &client, &shipper, &carrier);
-        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        let mut legs = shipments.get_legs(&SHIPMENT_ID);
+        legs.set(
+            1,
+            leg(&second, AMOUNT - FIRST_LEG, ShipmentStatus::Completed),
+        );
+        shipments.put_legs(&SHIPMENT_ID, &legs);
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::Completed;
+        shipments.put(&shipment);
 
-        let record = client.get_escrow(&SHIPMENT_ID);
-        assert_eq!(record.status, EscrowStatus::Disputed);
+        assert_eq!(
+            client.try_settle_from_shipment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::PaidPerLeg))
+        );
+    }
+
+    #[test]
+    fn test_partially_completed_multileg_shipment_splits_per_ruling() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        let shipments = fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
+
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.status = ShipmentStatus::PartiallyCompleted;
+        shipments.put(&shipment);
+        assert_eq!(
+            client.try_settle_from_shipment(&SHIPMENT_ID),
+            Err(Ok(EscrowError::ShipmentNotSettled))
+        );
+
+        // Half to the carriers, split between the legs by price.
+        shipments.put_ruling(&SHIPMENT_ID, &5_000);
+        client.settle_from_shipment(&SHIPMENT_ID);
 
-        client.resolve_dispute(&SHIPMENT_ID, &true);
         let token = TokenClient::new(&env, &token_addr);
-        assert_eq!(token.balance(&carrier), AMOUNT);
+        assert_eq!(token.balance(&carrier), FIRST_LEG / 2);
+        assert_eq!(token.balance(&second), (AMOUNT - FIRST_LEG) / 2);
+        assert_eq!(token.balance(&shipper), AMOUNT / 2);
+        let record = client.get_escrow(&SHIPMENT_ID);
+        assert_eq!(record.status, EscrowStatus::Split);
+        assert_solvent(&client, &token_addr);
     }
 
     #[test]
-    fn test_dispute_resolved_to_shipper() {
-        let (env, _admin, shipper, carrier, token_addr, client) = setup(AMOUNT);
+    fn test_multileg_refund_returns_unpaid_legs() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
 
-        fund(&env, &token_addr, &client, &shipper, &carrier);
-        client.raise_dispute(&carrier, &SHIPMENT_ID);
-        client.resolve_dispute(&SHIPMENT_ID, &false);
+        client.release_leg(&SHIPMENT_ID, &0);
+        client.refund_payment(&SHIPMENT_ID);
 
         let token = TokenClient::new(&env, &token_addr);
-        assert_eq!(token.balance(&shipper), AMOUNT);
+        assert_eq!(token.balance(&carrier), FIRST_LEG);
+        assert_eq!(token.balance(&shipper), AMOUNT - FIRST_LEG);
+        assert_eq!(token.balance(&second), 0);
+        assert_solvent(&client, &token_addr);
     }
 
     #[test]
-    fn test_double_fund_fails() {
-        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT * 2);
-
-        fund(&env, &token_addr, &client, &shipper, &carrier);
-        let result = client.try_fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &AMOUNT);
-        assert_eq!(result, Err(Ok(EscrowError::AlreadyFunded)));
+    fn test_multileg_funding_needs_legs() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        assert_eq!(
+            client.try_fund_escrow_multileg(&shipper, &carrier, &SHIPMENT_ID, &token_addr),
+            Err(Ok(EscrowError::ShipmentContractNotSet))
+        );
+        link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Accepted,
+        );
+        approve(&env, &token_addr, &client, &shipper);
+        assert_eq!(
+            client.try_fund_escrow_multileg(&shipper, &carrier, &SHIPMENT_ID, &token_addr),
+            Err(Ok(EscrowError::LegNotFound))
+        );
+        assert_eq!(
+            client.try_fund_escrow_multileg(
+                &shipper,
+                &Address::generate(&env),
+                &SHIPMENT_ID,
+                &token_addr
+            ),
+            Err(Ok(EscrowError::PartyMismatch))
+        );
     }
 
     #[test]
-    fn test_release_unfunded_fails() {
-        let (_, _, _, _, _, client) = setup(AMOUNT);
+    fn test_leg_pays_only_the_carrier_fixed_at_funding() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        let shipments = fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
+        assert_eq!(
+            client.get_escrow(&SHIPMENT_ID).legs.get(1).unwrap().carrier,
+            second
+        );
 
-        let result = client.try_release_payment(&SHIPMENT_ID);
-        assert_eq!(result, Err(Ok(EscrowError::NotFound)));
+        let intruder = Address::generate(&env);
+        let mut legs = shipments.get_legs(&SHIPMENT_ID);
+        legs.set(
+            1,
+            leg(&intruder, AMOUNT - FIRST_LEG, ShipmentStatus::Completed),
+        );
+        shipments.put_legs(&SHIPMENT_ID, &legs);
+        assert_eq!(
+            client.try_release_leg(&SHIPMENT_ID, &1),
+            Err(Ok(EscrowError::PartyMismatch))
+        );
+        assert_eq!(TokenClient::new(&env, &token_addr).balance(&intruder), 0);
     }
 
     #[test]
-    fn test_invalid_amount_fails() {
-        let (_, _, shipper, carrier, _, client) = setup(AMOUNT);
+    fn test_multileg_funding_checks_each_leg() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let shipments = link_shipments(
+            &env,
+            &client,
+            &shipper,
+            Some(carrier.clone()),
+            ShipmentStatus::Accepted,
+        );
+        approve(&env, &token_addr, &client, &shipper);
+        let fund =
+            || client.try_fund_escrow_multileg(&shipper, &carrier, &SHIPMENT_ID, &token_addr);
 
-        let result = client.try_fund_escrow(&shipper, &carrier, &SHIPMENT_ID, &0i128);
-        assert_eq!(result, Err(Ok(EscrowError::InvalidAmount)));
+        let mut unassigned = leg(&carrier, AMOUNT - FIRST_LEG, ShipmentStatus::Created);
+        unassigned.carrier = None;
+        shipments.put_legs(
+            &SHIPMENT_ID,
+            &vec![
+                &env,
+                leg(&carrier, FIRST_LEG, ShipmentStatus::Accepted),
+                unassigned,
+            ],
+        );
+        assert_eq!(fund(), Err(Ok(EscrowError::LegUnassigned)));
+
+        shipments.put_legs(
+            &SHIPMENT_ID,
+            &vec![&env, leg(&carrier, 0, ShipmentStatus::Accepted)],
+        );
+        assert_eq!(fund(), Err(Ok(EscrowError::InvalidAmount)));
+
+        shipments.put_legs(
+            &SHIPMENT_ID,
+            &vec![
+                &env,
+                leg(&carrier, i128::MAX, ShipmentStatus::Accepted),
+                leg(&carrier, i128::MAX, ShipmentStatus::Accepted),
+            ],
+        );
+        assert_eq!(fund(), Err(Ok(EscrowError::InvalidAmount)));
+
+        let mut shipment = shipments.get_shipment(&SHIPMENT_ID);
+        shipment.carrier = None;
+        shipments.put(&shipment);
+        assert_eq!(fund(), Err(Ok(EscrowError::PartyMismatch)));
     }
 
     #[test]
-    fn test_get_admin_returns_configured_admin() {
-        let (_env, admin, _shipper, _carrier, _token_addr, client) = setup(AMOUNT);
-        assert_eq!(client.get_admin(), admin);
+    fn test_multileg_ruling_pays_each_leg_carrier() {
+        let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
+
+        client.raise_dispute(&shipper, &SHIPMENT_ID);
+        client.resolve_dispute(&SHIPMENT_ID, &true);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&carrier), FIRST_LEG);
+        assert_eq!(token.balance(&second), AMOUNT - FIRST_LEG);
+        assert_solvent(&client, &token_addr);
     }
 
     #[test]
-    fn test_unauthorized_dispute_fails() {
+    fn test_multileg_cancellation_compensates_each_leg_carrier() {
         let (env, _, shipper, carrier, token_addr, client) = setup(AMOUNT);
+        let second = Address::generate(&env);
+        fund_multileg(&env, &token_addr, &client, &shipper, &carrier, &second);
+        client.set_cancellation_fees(&CancellationFees {
+            pending: 0,
+            accepted: 0,
+            in_transit: 5_000,
+        });
 
-        fund(&env, &token_addr, &client, &shipper, &carrier);
-        let random = Address::generate(&env);
-        let result = client.try_raise_dispute(&random, &SHIPMENT_ID);
-        assert_eq!(result, Err(Ok(EscrowError::Unauthorized)));
+        client.cancel_escrow(&SHIPMENT_ID);
+
+        let token = TokenClient::new(&env, &token_addr);
+        assert_eq!(token.balance(&shipper), AMOUNT / 2);
+        assert_eq!(token.balance(&carrier), FIRST_LEG / 2);
+        assert_eq!(token.balance(&second), (AMOUNT - FIRST_LEG) / 2);
+        assert_solvent(&client, &token_addr);
+    }
+
+    #[test]
+    fn test_shipment_contract_relinked_through_proposals() {
+        let (env, _, _, _, _, client) = setup(AMOUNT);
+        let (s1, s2, _) = setup_multisig(&env, &client);
+        let shipments = Address::generate(&env);
+
+        assert_eq!(
+            client.try_set_shipment_contract(&shipments),
+            Err(Ok(EscrowError::MultisigRequired))
+        );
+        let id = client.propose_action(
+            &s1,
+            &0,
+            &EscrowAction::SetShipmentContract(shipments.clone()),
+        );
+        client.approve_action(&s2, &id);
+        client.execute_proposal(&id);
+        assert_eq!(client.get_shipment_contract(), Some(shipments));
     }
 }
@@ -1,6 +1,14 @@
 #![no_std]
 
-use soroban_sdk::{contract, contracterror, contractimpl, contracttype, Address, BytesN, Env};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env,
+    IntoVal, Symbol, Val,
+};
+
+pub use admin_transfer::{
+    AdminChangedEvent, AdminProposedEvent, PendingAdmin, ADMIN_TRANSFER_WINDOW,
+};
+use admin_transfer::{AdminTransfer, TransferError};
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -10,17 +18,93 @@ pub enum IdentityError {
     NotRegistered = 2,
     Unauthorized = 3,
     NotInitialized = 4,
+    /// No admin transfer is pending.
+    NoPendingAdmin = 5,
+    /// The pending admin transfer was not accepted in time.
+    AdminTransferExpired = 6,
+}
+
+impl From<TransferError> for IdentityError {
+    fn from(e: TransferError) -> Self {
+        match e {
+            TransferError::NotInitialized => IdentityError::NotInitialized,
+            TransferError::NoPendingAdmin => IdentityError::NoPendingAdmin,
+            TransferError::Expired => IdentityError::AdminTransferExpired,
+            TransferError::NotNominee => IdentityError::Unauthorized,
+        }
+    }
 }
 
 #[contracttype]
 pub enum DataKey {
     Identity(Address),
     Admin,
+    SchemaVersion,
+    PendingAdmin,
 }
 
 // ~1 year in ledgers at ~5 second ledger time
 const LEDGER_PER_YEAR: u32 = 6_307_200;
 
+/// Version of this contract's code; bumped with every release.
+pub const CONTRACT_VERSION: u32 = 1;
+/// Layout of the wallet → `user_id_hash` records.  Still the launch layout, 1;
+/// a contract that never stored the marker holds the same records.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Events: topics are `("identity", <name>, EVENT_VERSION)`.
+//
+//   created    CreatedEvent
+//   revoked    RevokedEvent
+//   upgraded   UpgradedEvent
+//   migrated   MigratedEvent
+//   admin_new  AdminProposedEvent
+//   admin_set  AdminChangedEvent
+//   admin_cxl  AdminProposedEvent
+
+/// Bumped whenever a payload layout changes so indexers can branch on it.
+pub const EVENT_VERSION: u32 = 1;
+
+const EVENT_NS: Symbol = symbol_short!("identity");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreatedEvent {
+    pub wallet: Address,
+    pub user_id_hash: BytesN<32>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevokedEvent {
+    pub wallet: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+    /// `CONTRACT_VERSION` of the code being replaced.
+    pub from_version: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigratedEvent {
+    pub schema_version: u32,
+}
+
+fn emit(env: &Env, name: Symbol, data: impl IntoVal<Env, Val>) {
+    env.events().publish((EVENT_NS, name, EVENT_VERSION), data);
+}
+
+const ADMIN_TRANSFER: AdminTransfer<DataKey> = AdminTransfer {
+    admin_key: DataKey::Admin,
+    pending_key: DataKey::PendingAdmin,
+    event_ns: EVENT_NS,
+    event_version: EVENT_VERSION,
+};
+
 #[contract]
 pub struct IdentityContract;
 
@@ -32,6 +116,9 @@ impl IdentityContract {
             return Err(IdentityError::AlreadyRegistered);
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
         Ok(())
     }
 
@@ -56,11 +143,19 @@ impl IdentityContract {
             .set(&DataKey::Identity(wallet.clone()), &user_id_hash);
 
         env.storage().persistent().extend_ttl(
-            &DataKey::Identity(wallet),
+            &DataKey::Identity(wallet.clone()),
             LEDGER_PER_YEAR,
             LEDGER_PER_YEAR,
         );
 
+        emit(
+            &env,
+            symbol_short!("created"),
+            CreatedEvent {
+                wallet,
+                user_id_hash,
+            },
+        );
         Ok(())
     }
 
@@ -97,18 +192,105 @@ impl IdentityContract {
 
         env.storage()
             .persistent()
-            .remove(&DataKey::Identity(wallet));
+            .remove(&DataKey::Identity(wallet.clone()));
+
+        emit(&env, symbol_short!("revoked"), RevokedEvent { wallet });
+        Ok(())
+    }
+
+    /// Admin nominates a successor, who must `accept_admin` within
+    /// `ADMIN_TRANSFER_WINDOW`.  Replaces any earlier nomination.
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), IdentityError> {
+        Ok(ADMIN_TRANSFER.propose(&env, new_admin)?)
+    }
+
+    /// The nominated admin takes over.  Must be signed by them.
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), IdentityError> {
+        Ok(ADMIN_TRANSFER.accept(&env, new_admin)?)
+    }
+
+    /// Admin withdraws a pending nomination.
+    pub fn cancel_admin_transfer(env: Env) -> Result<(), IdentityError> {
+        Ok(ADMIN_TRANSFER.cancel(&env)?)
+    }
+
+    /// Admin swaps in new contract code; storage is kept.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), IdentityError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(IdentityError::NotInitialized)?;
+        admin.require_auth();
 
+        emit(
+            &env,
+            symbol_short!("upgraded"),
+            UpgradedEvent {
+                wasm_hash: new_wasm_hash.clone(),
+                from_version: CONTRACT_VERSION,
+            },
+        );
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
         Ok(())
     }
+
+    /// Admin moves the schema marker after an `upgrade`.  Identity records
+    /// have never changed shape, so this only records the version and does
+    /// nothing while the marker is current.
+    pub fn migrate(env: Env) -> Result<(), IdentityError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(IdentityError::NotInitialized)?;
+        admin.require_auth();
+
+        if Self::schema_version(env.clone()) < SCHEMA_VERSION {
+            env.storage()
+                .instance()
+                .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+            emit(
+                &env,
+                symbol_short!("migrated"),
+                MigratedEvent {
+                    schema_version: SCHEMA_VERSION,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    pub fn get_admin(env: Env) -> Result<Address, IdentityError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(IdentityError::NotInitialized)
+    }
+
+    pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
+        ADMIN_TRANSFER.pending(&env)
+    }
+
+    pub fn version(_env: Env) -> u32 {
+        CONTRACT_VERSION
+    }
+
+    /// Storage layout the stored records are known to be in.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(1)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use soroban_sdk::{
-        testutils::{Address as _, BytesN as _},
-        Env,
+        testutils::{Address as _, BytesN as _, Events},
+        vec, Env,
     };
 
     #[test]
@@ -185,4 +367,78 @@ mod tests {
         let result = client.try_get_user_identity(&wallet);
         assert_eq!(result, Err(Ok(IdentityError::NotRegistered)));
     }
+
+    #[test]
+    fn test_register_and_revoke_emit_events() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register(IdentityContract {}, ());
+        let client = IdentityContractClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let wallet = Address::generate(&env);
+        let hash = BytesN::random(&env);
+
+        client.initialize(&admin);
+        client.register_identity(&hash, &wallet);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    contract_id.clone(),
+                    (EVENT_NS, symbol_short!("created"), EVENT_VERSION).into_val(&env),
+                    CreatedEvent {
+                        wallet: wallet.clone(),
+                        user_id_hash: hash,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.revoke_identity(&wallet);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    contract_id.clone(),
+                    (EVENT_NS, symbol_short!("revoked"), EVENT_VERSION).into_val(&env),
+                    RevokedEvent { wallet }.into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upgrade_requires_admin() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let client = IdentityContractClient::new(&env, &env.register(IdentityContract {}, ()));
+        client.initialize(&Address::generate(&env));
+        assert_eq!(client.version(), CONTRACT_VERSION);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+
+        env.mock_auths(&[]);
+        assert!(client
+            .try_upgrade(&BytesN::from_array(&env, &[0; 32]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_migrate_is_a_no_op_on_the_current_schema() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let client = IdentityContractClient::new(&env, &env.register(IdentityContract {}, ()));
+        client.initialize(&Address::generate(&env));
+        client.migrate();
+        client.migrate();
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+        assert_eq!(env.events().all().len(), 0);
+
+        env.mock_auths(&[]);
+        assert!(client.try_migrate().is_err());
+    }
 }
@@ -13,7 +13,15 @@
 //! Fixed-point arithmetic: `average_rating` is stored as `score * 100`
 //! (i.e. 500 = 5.00 stars, 350 = 3.50 stars).
 
-use soroban_sdk::{contract, contracterror, contractimpl, contracttype, Address, Env, Vec};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env,
+    IntoVal, Symbol, Val, Vec,
+};
+
+pub use admin_transfer::{
+    AdminChangedEvent, AdminProposedEvent, PendingAdmin, ADMIN_TRANSFER_WINDOW,
+};
+use admin_transfer::{AdminTransfer, TransferError};
 
 // ── Errors ────────────────────────────────────────────────────────────────────
 
@@ -31,6 +39,21 @@ pub enum ReputationError {
     Unauthorized = 8,
     UserTypeMismatch = 9,
     RatingNotFound = 10,
+    /// No admin transfer is pending.
+    NoPendingAdmin = 11,
+    /// The pending admin transfer was not accepted in time.
+    AdminTransferExpired = 12,
+}
+
+impl From<TransferError> for ReputationError {
+    fn from(e: TransferError) -> Self {
+        match e {
+            TransferError::NotInitialized => ReputationError::NotInitialized,
+            TransferError::NoPendingAdmin => ReputationError::NoPendingAdmin,
+            TransferError::Expired => ReputationError::AdminTransferExpired,
+            TransferError::NotNominee => ReputationError::Unauthorized,
+        }
+    }
 }
 
 // ── Types ─────────────────────────────────────────────────────────────────────
@@ -86,10 +109,91 @@ pub enum DataKey {
     Reputation(Address),
     Rating(u64),
     ShipmentRaters(u64), // Vec<Address> — who has already rated this shipment
+    SchemaVersion,
+    PendingAdmin,
 }
 
 const TTL_LEDGERS: u32 = 6_307_200; // ~1 year
 
+/// Version of this contract's code; bumped with every release.
+pub const CONTRACT_VERSION: u32 = 1;
+/// Layout of reputations, ratings and per-shipment rater lists.  1 is both
+/// the launch layout and the current one; the marker defaults to it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// ── Events ────────────────────────────────────────────────────────────────────
+//
+// Topics are `("reputatn", <name>, EVENT_VERSION)`; the data is one of the
+// payload structs below.
+//
+//   created    CreatedEvent
+//   rated      RatedEvent
+//   stats      StatsEvent
+//   upgraded   UpgradedEvent
+//   migrated   MigratedEvent
+//   admin_new  AdminProposedEvent
+//   admin_set  AdminChangedEvent
+//   admin_cxl  AdminProposedEvent
+
+/// Bumped whenever a payload layout changes so indexers can branch on it.
+pub const EVENT_VERSION: u32 = 1;
+
+// `symbol_short!` caps at 9 characters.
+const EVENT_NS: Symbol = symbol_short!("reputatn");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreatedEvent {
+    pub user: Address,
+    pub user_type: UserType,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RatedEvent {
+    pub rating_id: u64,
+    pub shipment_id: u64,
+    pub rater: Address,
+    pub rated: Address,
+    pub score: u32,
+    /// The rated user's average after this rating (×100).
+    pub average_rating: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatsEvent {
+    pub user: Address,
+    pub was_on_time: bool,
+    pub was_successful: bool,
+    pub total_completed: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+    /// `CONTRACT_VERSION` of the code being replaced.
+    pub from_version: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigratedEvent {
+    pub schema_version: u32,
+}
+
+fn emit(env: &Env, name: Symbol, data: impl IntoVal<Env, Val>) {
+    env.events().publish((EVENT_NS, name, EVENT_VERSION), data);
+}
+
+const ADMIN_TRANSFER: AdminTransfer<DataKey> = AdminTransfer {
+    admin_key: DataKey::Admin,
+    pending_key: DataKey::PendingAdmin,
+    event_ns: EVENT_NS,
+    event_version: EVENT_VERSION,
+};
+
 // ── Contract ──────────────────────────────────────────────────────────────────
 
 #[contract]
@@ -117,6 +221,9 @@ impl ReputationContract {
         env.storage()
             .persistent()
             .set(&DataKey::RatingCounter, &0u64);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
         Ok(())
     }
 
@@ -140,7 +247,7 @@ impl ReputationContract {
 
         let rep = Reputation {
             user: user.clone(),
-            user_type,
+            user_type: user_type.clone(),
             total_completed: 0,
             total_rating_points: 0,
             rating_count: 0,
@@ -155,10 +262,17 @@ impl ReputationContract {
         env.storage()
             .persistent()
             .set(&DataKey::Reputation(user.clone()), &rep);
-        env.storage()
-            .persistent()
-            .extend_ttl(&DataKey::Reputation(user), TTL_LEDGERS, TTL_LEDGERS);
+        env.storage().persistent().extend_ttl(
+            &DataKey::Reputation(user.clone()),
+            TTL_LEDGERS,
+            TTL_LEDGERS,
+        );
 
+        emit(
+            &env,
+            symbol_short!("created"),
+            CreatedEvent { user, user_type },
+        );
         Ok(())
     }
 
@@ -230,7 +344,7 @@ impl ReputationContract {
         );
 
         // Mark rater for this shipment.
-        raters.push_back(rater);
+        raters.push_back(rater.clone());
         env.storage()
             .persistent()
             .set(&DataKey::ShipmentRaters(shipment_id), &raters);
@@ -251,11 +365,23 @@ impl ReputationContract {
             .persistent()
             .set(&DataKey::Reputation(rated.clone()), &rep);
         env.storage().persistent().extend_ttl(
-            &DataKey::Reputation(rated),
+            &DataKey::Reputation(rated.clone()),
             TTL_LEDGERS,
             TTL_LEDGERS,
         );
 
+        emit(
+            &env,
+            symbol_short!("rated"),
+            RatedEvent {
+                rating_id,
+                shipment_id,
+                rater,
+                rated,
+                score,
+                average_rating: rep.average_rating,
+            },
+        );
         Ok(rating_id)
     }
 
@@ -322,10 +448,22 @@ impl ReputationContract {
         env.storage()
             .persistent()
             .set(&DataKey::Reputation(user.clone()), &rep);
-        env.storage()
-            .persistent()
-            .extend_ttl(&DataKey::Reputation(user), TTL_LEDGERS, TTL_LEDGERS);
+        env.storage().persistent().extend_ttl(
+            &DataKey::Reputation(user.clone()),
+            TTL_LEDGERS,
+            TTL_LEDGERS,
+        );
 
+        emit(
+            &env,
+            symbol_short!("stats"),
+            StatsEvent {
+                user,
+                was_on_time,
+                was_successful,
+                total_completed: rep.total_completed,
+            },
+        );
         Ok(())
     }
 
@@ -377,8 +515,98 @@ impl ReputationContract {
         Ok((rating_component + rate_component + completion_component).min(1000))
     }
 
+    // ── Admin ─────────────────────────────────────────────────────────────
+
+    /// Admin nominates a successor, who must `accept_admin` within
+    /// `ADMIN_TRANSFER_WINDOW`.  Replaces any earlier nomination.
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), ReputationError> {
+        Ok(ADMIN_TRANSFER.propose(&env, new_admin)?)
+    }
+
+    /// The nominated admin takes over.  Must be signed by them.
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), ReputationError> {
+        Ok(ADMIN_TRANSFER.accept(&env, new_admin)?)
+    }
+
+    /// Admin withdraws a pending nomination.
+    pub fn cancel_admin_transfer(env: Env) -> Result<(), ReputationError> {
+        Ok(ADMIN_TRANSFER.cancel(&env)?)
+    }
+
+    // ── Upgrades ──────────────────────────────────────────────────────────
+
+    /// Admin swaps in new contract code; storage is kept.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), ReputationError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ReputationError::NotInitialized)?;
+        admin.require_auth();
+
+        emit(
+            &env,
+            symbol_short!("upgraded"),
+            UpgradedEvent {
+                wasm_hash: new_wasm_hash.clone(),
+                from_version: CONTRACT_VERSION,
+            },
+        );
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(())
+    }
+
+    /// Admin runs this after an `upgrade` that raises `SCHEMA_VERSION`.
+    /// Rating records are unchanged since launch, so there is nothing to
+    /// rewrite and calling it on a current deployment changes nothing.
+    pub fn migrate(env: Env) -> Result<(), ReputationError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ReputationError::NotInitialized)?;
+        admin.require_auth();
+
+        if Self::schema_version(env.clone()) < SCHEMA_VERSION {
+            env.storage()
+                .instance()
+                .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+            emit(
+                &env,
+                symbol_short!("migrated"),
+                MigratedEvent {
+                    schema_version: SCHEMA_VERSION,
+                },
+            );
+        }
+        Ok(())
+    }
+
     // ── Queries ───────────────────────────────────────────────────────────
 
+    pub fn get_admin(env: Env) -> Result<Address, ReputationError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ReputationError::NotInitialized)
+    }
+
+    pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
+        ADMIN_TRANSFER.pending(&env)
+    }
+
+    pub fn version(_env: Env) -> u32 {
+        CONTRACT_VERSION
+    }
+
+    /// Storage layout the stored records are known to be in.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(1)
+    }
+
     pub fn get_reputation(env: Env, user: Address) -> Result<Reputation, ReputationError> {
         env.storage()
             .persistent()
@@ -432,7 +660,10 @@ impl ReputationContract {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::{testutils::Address as _, Env};
+    use soroban_sdk::{
+        testutils::{Address as _, Events},
+        vec, BytesN, Env,
+    };
 
     fn setup() -> (Env, Address, Address, ReputationContractClient<'static>) {
         let env = Env::default();
@@ -655,4 +886,93 @@ mod tests {
         client.submit_rating(&rater2, &2u64, &rated, &3u32);
         assert_eq!(client.get_total_ratings(), 2);
     }
+
+    #[test]
+    fn test_mutations_emit_events() {
+        let (env, _, auth_contract, client) = setup();
+        let rater = Address::generate(&env);
+        let carrier = Address::generate(&env);
+
+        client.register_user(&carrier, &UserType::Carrier);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (EVENT_NS, symbol_short!("created"), EVENT_VERSION).into_val(&env),
+                    CreatedEvent {
+                        user: carrier.clone(),
+                        user_type: UserType::Carrier,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.register_user(&rater, &UserType::Shipper);
+        let rating_id = client.submit_rating(&rater, &9u64, &carrier, &4u32);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (EVENT_NS, symbol_short!("rated"), EVENT_VERSION).into_val(&env),
+                    RatedEvent {
+                        rating_id,
+                        shipment_id: 9,
+                        rater: rater.clone(),
+                        rated: carrier.clone(),
+                        score: 4,
+                        average_rating: 400,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.update_stats(&auth_contract, &carrier, &true, &false);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (EVENT_NS, symbol_short!("stats"), EVENT_VERSION).into_val(&env),
+                    StatsEvent {
+                        user: carrier.clone(),
+                        was_on_time: true,
+                        was_successful: false,
+                        total_completed: 1,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upgrade_requires_admin() {
+        let (env, _, _, client) = setup();
+        assert_eq!(client.version(), CONTRACT_VERSION);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+
+        env.mock_auths(&[]);
+        assert!(client
+            .try_upgrade(&BytesN::from_array(&env, &[0; 32]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_migrate_is_a_no_op_on_the_current_schema() {
+        let (env, _, _, client) = setup();
+        client.migrate();
+        client.migrate();
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+        assert_eq!(env.events().all().len(), 0);
+
+        env.mock_auths(&[]);
+        assert!(client.try_migrate().is_err());
+    }
 }
@@ -1,6 +1,16 @@
 #![no_std]
+// `create_shipment` takes the full posting; the generated client mirrors it.
+#![allow(clippy::too_many_arguments)]
 
-use soroban_sdk::{contract, contracterror, contractimpl, contracttype, Address, Env, String, Vec};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env,
+    IntoVal, Map, String, Symbol, Val, Vec,
+};
+
+pub use admin_transfer::{
+    AdminChangedEvent, AdminProposedEvent, PendingAdmin, ADMIN_TRANSFER_WINDOW,
+};
+use admin_transfer::{AdminTransfer, TransferError};
 
 // ── Errors ────────────────────────────────────────────────────────────────────
 
@@ -16,20 +26,46 @@ pub enum ShipmentError {
     InvalidInput = 6,
     NotCarrier = 7,
     NotShipper = 8,
+    /// The entry point is frozen by the circuit breaker.
+    Paused = 9,
+    /// No admin transfer is pending.
+    NoPendingAdmin = 10,
+    /// The pending admin transfer was not accepted in time.
+    AdminTransferExpired = 11,
+    /// The shipment is split into legs; use the per-leg entry points.
+    MultiLeg = 12,
+    /// The shipment has no leg at that index.
+    LegNotFound = 13,
+    /// `migrate` must resume where the previous batch stopped.
+    MigrationOutOfOrder = 14,
+}
+
+impl From<TransferError> for ShipmentError {
+    fn from(e: TransferError) -> Self {
+        match e {
+            TransferError::NotInitialized => ShipmentError::NotInitialized,
+            TransferError::NoPendingAdmin => ShipmentError::NoPendingAdmin,
+            TransferError::Expired => ShipmentError::AdminTransferExpired,
+            TransferError::NotNominee => ShipmentError::Unauthorized,
+        }
+    }
 }
 
 // ── Types ─────────────────────────────────────────────────────────────────────
 
+// `ShipmentStatus` and `Shipment` are mirrored in the escrow contract, which
+// reads `get_shipment` to settle trustlessly — keep the two in sync.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ShipmentStatus {
-    Created,   // Shipper posted, awaiting carrier
-    Accepted,  // Carrier accepted, awaiting pickup
-    InTransit, // Carrier has picked up cargo
-    Delivered, // Carrier marked as delivered, awaiting shipper confirm
-    Completed, // Shipper confirmed delivery — triggers payment release
-    Disputed,  // Either party raised a dispute
-    Cancelled, // Cancelled by shipper (only from Created or Accepted)
+    Created,            // Shipper posted, awaiting carrier
+    Accepted,           // Carrier accepted, awaiting pickup
+    InTransit,          // Carrier has picked up cargo
+    Delivered,          // Carrier marked as delivered, awaiting shipper confirm
+    Completed,          // Shipper confirmed delivery — triggers payment release
+    Disputed,           // Either party raised a dispute
+    Cancelled,          // Cancelled by shipper (only from Created or Accepted)
+    PartiallyCompleted, // Dispute ruled with the payment split between the parties
 }
 
 #[contracttype]
@@ -47,6 +83,77 @@ pub struct Shipment {
     pub status: ShipmentStatus,
     pub created_at: u64,
     pub updated_at: u64,
+    /// Promised delivery time; `0` when the shipper set none.
+    pub deliver_by: u64,
+    pub penalty: LatePenalty,
+    /// When the cargo went in transit (the first leg's pickup on a multi-leg
+    /// shipment); `0` until then.
+    pub picked_up_at: u64,
+    /// When the carrier marked the shipment delivered; `0` until then.
+    pub delivered_at: u64,
+}
+
+/// Late-delivery penalty the escrow deducts from the carrier's payout.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LatePenalty {
+    /// Charged per full hour past `deliver_by`, in basis points of the payout.
+    pub bps_per_hour: u32,
+    /// Cap on the total penalty, in basis points.
+    pub max_bps: u32,
+}
+
+/// One hop of a multi-leg shipment, e.g. origin to a cross-dock hub.  Legs
+/// run in order: a leg's carrier picks up once the previous leg has been
+/// delivered to the hub.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Leg {
+    pub carrier: Option<Address>,
+    /// This leg's share of the shipment price, in stroops.
+    pub price: i128,
+    /// `Created` through `Completed`; disputes are raised on the shipment.
+    pub status: ShipmentStatus,
+    /// When the carrier marked the leg delivered; `0` until then.
+    pub delivered_at: u64,
+}
+
+/// `Shipment` as stored under schema 1, before delivery promises.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShipmentV1 {
+    pub id: u64,
+    pub shipper: Address,
+    pub carrier: Option<Address>,
+    pub origin: String,
+    pub destination: String,
+    pub cargo_description: String,
+    pub weight_kg: u32,
+    pub price: i128,
+    pub status: ShipmentStatus,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// Which group of entry points `pause` / `unpause` acts on.  Scopes line up
+/// with the escrow contract's so one incident response can freeze both.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PauseScope {
+    /// New commitments that lead to funding: `create_shipment`, `accept_shipment`.
+    Funding,
+    /// Outcomes escrow settles on: `confirm_delivery`, `cancel_shipment`,
+    /// `resolve_dispute`.
+    Settlement,
+    /// Both of the above plus the in-flight transitions and `raise_dispute`.
+    All,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PauseState {
+    pub funding: bool,
+    pub settlement: bool,
 }
 
 #[contracttype]
@@ -56,10 +163,115 @@ pub enum DataKey {
     Shipment(u64),
     ShipperList(Address),
     CarrierList(Address),
+    Pauser,
+    PauseState,
+    Ruling(u64), // carrier share in basis points for a split dispute outcome
+    SchemaVersion,
+    PendingAdmin,
+    Legs(u64),       // ordered legs; absent for single-carrier shipments
+    MigrationCursor, // next id `migrate` expects; absent when none is running
 }
 
 const TTL_LEDGERS: u32 = 6_307_200; // ~1 year at ~5 s/ledger
 
+/// Version of this contract's code; bumped with every release.
+pub const CONTRACT_VERSION: u32 = 2;
+/// Layout of persisted records.  1 = launch layout; 2 adds the delivery promise and
+/// late penalty to `Shipment`.
+/// `schema_version` reports 1 for launch deployments, which never wrote it.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Most legs a shipment may be split into.
+pub const MAX_LEGS: u32 = 8;
+
+// ── Events ────────────────────────────────────────────────────────────────────
+//
+// Topics are `("shipment", <name>, EVENT_VERSION)`; the data is one of the
+// payload structs below.
+//
+//   created    CreatedEvent
+//   status     StatusEvent — one per status transition
+//   leg        LegEvent — one per leg transition
+//   pauser     PauserEvent
+//   paused     PauseEvent
+//   unpaused   PauseEvent
+//   upgraded   UpgradedEvent
+//   migrated   MigratedEvent
+//   admin_new  AdminProposedEvent
+//   admin_set  AdminChangedEvent
+//   admin_cxl  AdminProposedEvent
+
+/// Bumped whenever a payload layout changes so indexers can branch on it.
+pub const EVENT_VERSION: u32 = 1;
+
+const EVENT_NS: Symbol = symbol_short!("shipment");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreatedEvent {
+    pub shipment_id: u64,
+    pub shipper: Address,
+    pub price: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatusEvent {
+    pub shipment_id: u64,
+    pub from: ShipmentStatus,
+    pub to: ShipmentStatus,
+    /// Address whose authorisation drove the transition.
+    pub actor: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegEvent {
+    pub shipment_id: u64,
+    pub index: u32,
+    pub from: ShipmentStatus,
+    pub to: ShipmentStatus,
+    pub actor: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PauserEvent {
+    pub pauser: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PauseEvent {
+    pub scope: PauseScope,
+    pub by: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+    /// `CONTRACT_VERSION` of the code being replaced.
+    pub from_version: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigratedEvent {
+    pub schema_version: u32,
+}
+
+fn emit(env: &Env, name: Symbol, data: impl IntoVal<Env, Val>) {
+    env.events().publish((EVENT_NS, name, EVENT_VERSION), data);
+}
+
+const ADMIN_TRANSFER: AdminTransfer<DataKey> = AdminTransfer {
+    admin_key: DataKey::Admin,
+    pending_key: DataKey::PendingAdmin,
+    event_ns: EVENT_NS,
+    event_version: EVENT_VERSION,
+};
+
 // ── Contract ──────────────────────────────────────────────────────────────────
 
 #[contract]
@@ -76,6 +288,9 @@ impl ShipmentContract {
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().persistent().set(&DataKey::Counter, &0u64);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
         Ok(())
     }
 
@@ -90,15 +305,21 @@ impl ShipmentContract {
         cargo_description: String,
         weight_kg: u32,
         price: i128,
+        deliver_by: u64,
+        penalty: LatePenalty,
     ) -> Result<u64, ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::Funding)?;
         shipper.require_auth();
 
+        let now = env.ledger().timestamp();
         if weight_kg == 0 || price <= 0 {
             return Err(ShipmentError::InvalidInput);
         }
+        if (deliver_by != 0 && deliver_by <= now) || penalty.max_bps > 10_000 {
+            return Err(ShipmentError::InvalidInput);
+        }
 
         let id = Self::next_id(&env);
-        let now = env.ledger().timestamp();
 
         let shipment = Shipment {
             id,
@@ -112,6 +333,10 @@ impl ShipmentContract {
             status: ShipmentStatus::Created,
             created_at: now,
             updated_at: now,
+            deliver_by,
+            penalty,
+            picked_up_at: 0,
+            delivered_at: 0,
         };
 
         env.storage()
@@ -121,8 +346,17 @@ impl ShipmentContract {
             .persistent()
             .extend_ttl(&DataKey::Shipment(id), TTL_LEDGERS, TTL_LEDGERS);
 
-        Self::append_to_list(&env, DataKey::ShipperList(shipper), id);
+        Self::append_to_list(&env, DataKey::ShipperList(shipper.clone()), id);
 
+        emit(
+            &env,
+            symbol_short!("created"),
+            CreatedEvent {
+                shipment_id: id,
+                shipper,
+                price,
+            },
+        );
         Ok(id)
     }
 
@@ -133,9 +367,11 @@ impl ShipmentContract {
         shipper: Address,
         shipment_id: u64,
     ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
         shipper.require_auth();
 
         let mut shipment = Self::load(&env, shipment_id)?;
+        Self::require_single_leg(&env, shipment_id)?;
 
         if shipment.shipper != shipper {
             return Err(ShipmentError::NotShipper);
@@ -144,9 +380,7 @@ impl ShipmentContract {
             return Err(ShipmentError::InvalidStatus);
         }
 
-        shipment.status = ShipmentStatus::Completed;
-        shipment.updated_at = env.ledger().timestamp();
-        Self::save(&env, &shipment);
+        Self::transition(&env, &mut shipment, ShipmentStatus::Completed, shipper);
         Ok(())
     }
 
@@ -156,6 +390,7 @@ impl ShipmentContract {
         shipper: Address,
         shipment_id: u64,
     ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
         shipper.require_auth();
 
         let mut shipment = Self::load(&env, shipment_id)?;
@@ -170,9 +405,7 @@ impl ShipmentContract {
             return Err(ShipmentError::InvalidStatus);
         }
 
-        shipment.status = ShipmentStatus::Cancelled;
-        shipment.updated_at = env.ledger().timestamp();
-        Self::save(&env, &shipment);
+        Self::transition(&env, &mut shipment, ShipmentStatus::Cancelled, shipper);
         Ok(())
     }
 
@@ -184,18 +417,23 @@ impl ShipmentContract {
         carrier: Address,
         shipment_id: u64,
     ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::Funding)?;
         carrier.require_auth();
 
         let mut shipment = Self::load(&env, shipment_id)?;
+        Self::require_single_leg(&env, shipment_id)?;
 
         if shipment.status != ShipmentStatus::Created {
             return Err(ShipmentError::InvalidStatus);
         }
 
         shipment.carrier = Some(carrier.clone());
-        shipment.status = ShipmentStatus::Accepted;
-        shipment.updated_at = env.ledger().timestamp();
-        Self::save(&env, &shipment);
+        Self::transition(
+            &env,
+            &mut shipment,
+            ShipmentStatus::Accepted,
+            carrier.clone(),
+        );
 
         Self::append_to_list(&env, DataKey::CarrierList(carrier), shipment_id);
         Ok(())
@@ -207,9 +445,11 @@ impl ShipmentContract {
         carrier: Address,
         shipment_id: u64,
     ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::All)?;
         carrier.require_auth();
 
         let mut shipment = Self::load(&env, shipment_id)?;
+        Self::require_single_leg(&env, shipment_id)?;
 
         if shipment.status != ShipmentStatus::Accepted {
             return Err(ShipmentError::InvalidStatus);
@@ -218,9 +458,7 @@ impl ShipmentContract {
             return Err(ShipmentError::NotCarrier);
         }
 
-        shipment.status = ShipmentStatus::InTransit;
-        shipment.updated_at = env.ledger().timestamp();
-        Self::save(&env, &shipment);
+        Self::transition(&env, &mut shipment, ShipmentStatus::InTransit, carrier);
         Ok(())
     }
 
@@ -230,9 +468,11 @@ impl ShipmentContract {
         carrier: Address,
         shipment_id: u64,
     ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::All)?;
         carrier.require_auth();
 
         let mut shipment = Self::load(&env, shipment_id)?;
+        Self::require_single_leg(&env, shipment_id)?;
 
         if shipment.status != ShipmentStatus::InTransit {
             return Err(ShipmentError::InvalidStatus);
@@ -241,9 +481,249 @@ impl ShipmentContract {
             return Err(ShipmentError::NotCarrier);
         }
 
-        shipment.status = ShipmentStatus::Delivered;
-        shipment.updated_at = env.ledger().timestamp();
-        Self::save(&env, &shipment);
+        shipment.delivered_at = env.ledger().timestamp();
+        Self::transition(&env, &mut shipment, ShipmentStatus::Delivered, carrier);
+        Ok(())
+    }
+
+    // ── Legs ──────────────────────────────────────────────────────────────
+
+    /// Shipper posts a shipment carried in `leg_prices.len()` consecutive
+    /// legs, each with its own carrier.  The shipment price is the sum of
+    /// the leg prices.  The shipment itself follows its legs: it is
+    /// `Accepted` with the first leg's carrier, `InTransit` from the first
+    /// pickup, `Delivered` when the final leg is, and `Completed` once the
+    /// shipper confirms the final leg.
+    pub fn create_multileg_shipment(
+        env: Env,
+        shipper: Address,
+        origin: String,
+        destination: String,
+        cargo_description: String,
+        weight_kg: u32,
+        leg_prices: Vec<i128>,
+        deliver_by: u64,
+        penalty: LatePenalty,
+    ) -> Result<u64, ShipmentError> {
+        if leg_prices.len() < 2 || leg_prices.len() > MAX_LEGS {
+            return Err(ShipmentError::InvalidInput);
+        }
+        let mut legs = Vec::new(&env);
+        let mut price: i128 = 0;
+        for leg_price in leg_prices.iter() {
+            if leg_price <= 0 {
+                return Err(ShipmentError::InvalidInput);
+            }
+            price = price
+                .checked_add(leg_price)
+                .ok_or(ShipmentError::InvalidInput)?;
+            legs.push_back(Leg {
+                carrier: None,
+                price: leg_price,
+                status: ShipmentStatus::Created,
+                delivered_at: 0,
+            });
+        }
+
+        let id = Self::create_shipment(
+            env.clone(),
+            shipper,
+            origin,
+            destination,
+            cargo_description,
+            weight_kg,
+            price,
+            deliver_by,
+            penalty,
+        )?;
+        Self::save_legs(&env, id, &legs);
+        Ok(id)
+    }
+
+    /// Carrier accepts one leg.  Legs are accepted in order, so the shipment
+    /// is `Accepted` as soon as any leg is and its cancellation stage follows.
+    pub fn accept_leg(
+        env: Env,
+        carrier: Address,
+        shipment_id: u64,
+        index: u32,
+    ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::Funding)?;
+        carrier.require_auth();
+
+        let mut shipment = Self::load(&env, shipment_id)?;
+        let mut legs = Self::load_legs(&env, shipment_id)?;
+        let mut leg = legs.get(index).ok_or(ShipmentError::LegNotFound)?;
+        let previous_open = index > 0 && legs.get(index - 1).unwrap().carrier.is_none();
+
+        if leg.status != ShipmentStatus::Created
+            || previous_open
+            || !matches!(
+                shipment.status,
+                ShipmentStatus::Created | ShipmentStatus::Accepted | ShipmentStatus::InTransit
+            )
+        {
+            return Err(ShipmentError::InvalidStatus);
+        }
+
+        leg.carrier = Some(carrier.clone());
+        legs.set(index, leg);
+        Self::transition_leg(
+            &env,
+            shipment_id,
+            &mut legs,
+            index,
+            ShipmentStatus::Accepted,
+            carrier.clone(),
+        );
+        if index == 0 {
+            // The first leg's carrier stands for the shipment as a whole.
+            shipment.carrier = Some(carrier.clone());
+            Self::transition(
+                &env,
+                &mut shipment,
+                ShipmentStatus::Accepted,
+                carrier.clone(),
+            );
+        }
+
+        if !Self::get_shipments_by_carrier(env.clone(), carrier.clone()).contains(shipment_id) {
+            Self::append_to_list(&env, DataKey::CarrierList(carrier), shipment_id);
+        }
+        Ok(())
+    }
+
+    /// Leg carrier picks up the cargo — from the origin for the first leg,
+    /// otherwise from the hub the previous leg delivered to.
+    pub fn mark_leg_in_transit(
+        env: Env,
+        carrier: Address,
+        shipment_id: u64,
+        index: u32,
+    ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::All)?;
+        carrier.require_auth();
+
+        let mut shipment = Self::load(&env, shipment_id)?;
+        let mut legs = Self::load_legs(&env, shipment_id)?;
+        let leg = legs.get(index).ok_or(ShipmentError::LegNotFound)?;
+
+        if leg.status != ShipmentStatus::Accepted
+            || !matches!(
+                shipment.status,
+                ShipmentStatus::Accepted | ShipmentStatus::InTransit
+            )
+        {
+            return Err(ShipmentError::InvalidStatus);
+        }
+        if leg.carrier.as_ref() != Some(&carrier) {
+            return Err(ShipmentError::NotCarrier);
+        }
+        if index > 0
+            && !matches!(
+                legs.get(index - 1).unwrap().status,
+                ShipmentStatus::Delivered | ShipmentStatus::Completed
+            )
+        {
+            return Err(ShipmentError::InvalidStatus);
+        }
+
+        Self::transition_leg(
+            &env,
+            shipment_id,
+            &mut legs,
+            index,
+            ShipmentStatus::InTransit,
+            carrier.clone(),
+        );
+        if shipment.status == ShipmentStatus::Accepted {
+            Self::transition(&env, &mut shipment, ShipmentStatus::InTransit, carrier);
+        }
+        Ok(())
+    }
+
+    /// Leg carrier hands the cargo over at the end of its leg.  Delivering
+    /// the final leg marks the shipment delivered.
+    pub fn mark_leg_delivered(
+        env: Env,
+        carrier: Address,
+        shipment_id: u64,
+        index: u32,
+    ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::All)?;
+        carrier.require_auth();
+
+        let mut shipment = Self::load(&env, shipment_id)?;
+        let mut legs = Self::load_legs(&env, shipment_id)?;
+        let mut leg = legs.get(index).ok_or(ShipmentError::LegNotFound)?;
+
+        if leg.status != ShipmentStatus::InTransit || shipment.status != ShipmentStatus::InTransit {
+            return Err(ShipmentError::InvalidStatus);
+        }
+        if leg.carrier.as_ref() != Some(&carrier) {
+            return Err(ShipmentError::NotCarrier);
+        }
+
+        let now = env.ledger().timestamp();
+        leg.delivered_at = now;
+        legs.set(index, leg);
+        Self::transition_leg(
+            &env,
+            shipment_id,
+            &mut legs,
+            index,
+            ShipmentStatus::Delivered,
+            carrier.clone(),
+        );
+        if index == legs.len() - 1 {
+            shipment.delivered_at = now;
+            Self::transition(&env, &mut shipment, ShipmentStatus::Delivered, carrier);
+        }
+        Ok(())
+    }
+
+    /// Shipper confirms a delivered leg, releasing that leg's escrow share.
+    /// Legs are confirmed in order; confirming the final leg completes the
+    /// shipment.
+    pub fn confirm_leg(
+        env: Env,
+        shipper: Address,
+        shipment_id: u64,
+        index: u32,
+    ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        shipper.require_auth();
+
+        let mut shipment = Self::load(&env, shipment_id)?;
+        let mut legs = Self::load_legs(&env, shipment_id)?;
+        let leg = legs.get(index).ok_or(ShipmentError::LegNotFound)?;
+
+        if shipment.shipper != shipper {
+            return Err(ShipmentError::NotShipper);
+        }
+        if leg.status != ShipmentStatus::Delivered
+            || !matches!(
+                shipment.status,
+                ShipmentStatus::InTransit | ShipmentStatus::Delivered
+            )
+        {
+            return Err(ShipmentError::InvalidStatus);
+        }
+        if index > 0 && legs.get(index - 1).unwrap().status != ShipmentStatus::Completed {
+            return Err(ShipmentError::InvalidStatus);
+        }
+
+        Self::transition_leg(
+            &env,
+            shipment_id,
+            &mut legs,
+            index,
+            ShipmentStatus::Completed,
+            shipper.clone(),
+        );
+        if index == legs.len() - 1 {
+            Self::transition(&env, &mut shipment, ShipmentStatus::Completed, shipper);
+        }
         Ok(())
     }
 
@@ -251,11 +731,16 @@ impl ShipmentContract {
 
     /// Either party can raise a dispute when the shipment is InTransit or Delivered.
     pub fn raise_dispute(env: Env, caller: Address, shipment_id: u64) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::All)?;
         caller.require_auth();
 
         let mut shipment = Self::load(&env, shipment_id)?;
 
-        let is_party = shipment.shipper == caller || shipment.carrier.as_ref() == Some(&caller);
+        let is_party = shipment.shipper == caller
+            || shipment.carrier.as_ref() == Some(&caller)
+            || Self::get_legs(env.clone(), shipment_id)
+                .iter()
+                .any(|leg| leg.carrier.as_ref() == Some(&caller));
 
         if !is_party {
             return Err(ShipmentError::Unauthorized);
@@ -267,9 +752,7 @@ impl ShipmentContract {
             return Err(ShipmentError::InvalidStatus);
         }
 
-        shipment.status = ShipmentStatus::Disputed;
-        shipment.updated_at = env.ledger().timestamp();
-        Self::save(&env, &shipment);
+        Self::transition(&env, &mut shipment, ShipmentStatus::Disputed, caller);
         Ok(())
     }
 
@@ -279,6 +762,195 @@ impl ShipmentContract {
         shipment_id: u64,
         resolve_as_completed: bool,
     ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ShipmentError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut shipment = Self::load(&env, shipment_id)?;
+
+        if shipment.status != ShipmentStatus::Disputed {
+            return Err(ShipmentError::InvalidStatus);
+        }
+
+        let outcome = if resolve_as_completed {
+            ShipmentStatus::Completed
+        } else {
+            ShipmentStatus::Cancelled
+        };
+        Self::transition(&env, &mut shipment, outcome, admin);
+        Ok(())
+    }
+
+    /// Admin resolves a dispute with a split: the carrier is paid
+    /// `carrier_bps` basis points and the shipper is refunded the rest.
+    /// Mirrors the escrow contract's `resolve_dispute_split`; all-or-nothing
+    /// rulings go through `resolve_dispute`.
+    pub fn resolve_dispute_partial(
+        env: Env,
+        shipment_id: u64,
+        carrier_bps: u32,
+    ) -> Result<(), ShipmentError> {
+        Self::require_not_paused(&env, PauseScope::Settlement)?;
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ShipmentError::NotInitialized)?;
+        admin.require_auth();
+
+        if carrier_bps == 0 || carrier_bps >= 10_000 {
+            return Err(ShipmentError::InvalidInput);
+        }
+
+        let mut shipment = Self::load(&env, shipment_id)?;
+
+        if shipment.status != ShipmentStatus::Disputed {
+            return Err(ShipmentError::InvalidStatus);
+        }
+
+        let key = DataKey::Ruling(shipment_id);
+        env.storage().persistent().set(&key, &carrier_bps);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+        Self::transition(
+            &env,
+            &mut shipment,
+            ShipmentStatus::PartiallyCompleted,
+            admin,
+        );
+        Ok(())
+    }
+
+    // ── Circuit breaker ───────────────────────────────────────────────────
+
+    /// Admin appoints the pauser — the key allowed to trip the breaker.
+    pub fn set_pauser(env: Env, pauser: Address) -> Result<(), ShipmentError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ShipmentError::NotInitialized)?;
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Pauser, &pauser);
+
+        emit(&env, symbol_short!("pauser"), PauserEvent { pauser });
+        Ok(())
+    }
+
+    /// Pauser (or admin) freezes the entry points in `scope`.
+    /// Read methods keep working.
+    pub fn pause(env: Env, caller: Address, scope: PauseScope) -> Result<(), ShipmentError> {
+        caller.require_auth();
+
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ShipmentError::NotInitialized)?;
+        let pauser: Option<Address> = env.storage().instance().get(&DataKey::Pauser);
+        if caller != admin && pauser.as_ref() != Some(&caller) {
+            return Err(ShipmentError::Unauthorized);
+        }
+
+        let mut state = Self::get_pause_state(env.clone());
+        match scope {
+            PauseScope::Funding => state.funding = true,
+            PauseScope::Settlement => state.settlement = true,
+            PauseScope::All => {
+                state.funding = true;
+                state.settlement = true;
+            }
+        }
+        env.storage().instance().set(&DataKey::PauseState, &state);
+
+        emit(
+            &env,
+            symbol_short!("paused"),
+            PauseEvent { scope, by: caller },
+        );
+        Ok(())
+    }
+
+    /// Admin-only: lift the freeze on `scope`.
+    pub fn unpause(env: Env, scope: PauseScope) -> Result<(), ShipmentError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ShipmentError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut state = Self::get_pause_state(env.clone());
+        match scope {
+            PauseScope::Funding => state.funding = false,
+            PauseScope::Settlement => state.settlement = false,
+            PauseScope::All => state = PauseState::default(),
+        }
+        env.storage().instance().set(&DataKey::PauseState, &state);
+
+        emit(
+            &env,
+            symbol_short!("unpaused"),
+            PauseEvent { scope, by: admin },
+        );
+        Ok(())
+    }
+
+    // ── Admin ─────────────────────────────────────────────────────────────
+
+    /// Admin nominates a successor, who must `accept_admin` within
+    /// `ADMIN_TRANSFER_WINDOW`.  Replaces any earlier nomination.
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), ShipmentError> {
+        Ok(ADMIN_TRANSFER.propose(&env, new_admin)?)
+    }
+
+    /// The nominated admin takes over.  Must be signed by them.
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), ShipmentError> {
+        Ok(ADMIN_TRANSFER.accept(&env, new_admin)?)
+    }
+
+    /// Admin withdraws a pending nomination.
+    pub fn cancel_admin_transfer(env: Env) -> Result<(), ShipmentError> {
+        Ok(ADMIN_TRANSFER.cancel(&env)?)
+    }
+
+    // ── Upgrades ──────────────────────────────────────────────────────────
+
+    /// Admin swaps in new contract code; storage is kept.  Run `migrate`
+    /// afterwards when the new code's `SCHEMA_VERSION` is higher.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), ShipmentError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ShipmentError::NotInitialized)?;
+        admin.require_auth();
+
+        emit(
+            &env,
+            symbol_short!("upgraded"),
+            UpgradedEvent {
+                wasm_hash: new_wasm_hash.clone(),
+                from_version: CONTRACT_VERSION,
+            },
+        );
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(())
+    }
+
+    /// Admin rewrites shipments `start_id .. start_id + limit` in the current
+    /// layout and returns the next id to pass.  Batches must run in order from
+    /// id 1: `start_id` has to be the id the previous batch returned.  Old
+    /// records are also upgraded on read, so batches can run while the
+    /// contract is live; the schema marker moves once every shipment has been
+    /// covered.
+    pub fn migrate(env: Env, start_id: u64, limit: u32) -> Result<u64, ShipmentError> {
         let admin: Address = env
             .storage()
             .instance()
@@ -286,28 +958,82 @@ impl ShipmentContract {
             .ok_or(ShipmentError::NotInitialized)?;
         admin.require_auth();
 
-        let mut shipment = Self::load(&env, shipment_id)?;
+        let cursor: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::MigrationCursor)
+            .unwrap_or(1);
+        if start_id != cursor {
+            return Err(ShipmentError::MigrationOutOfOrder);
+        }
 
-        if shipment.status != ShipmentStatus::Disputed {
-            return Err(ShipmentError::InvalidStatus);
+        let total = Self::get_total_shipments(env.clone());
+        let end = start_id.saturating_add(limit as u64).min(total + 1);
+        for id in start_id..end {
+            if env.storage().persistent().has(&DataKey::Shipment(id)) {
+                let shipment = Self::load(&env, id)?;
+                Self::save(&env, &shipment);
+            }
         }
 
-        shipment.status = if resolve_as_completed {
-            ShipmentStatus::Completed
+        if end <= total {
+            env.storage()
+                .instance()
+                .set(&DataKey::MigrationCursor, &end);
         } else {
-            ShipmentStatus::Cancelled
-        };
-        shipment.updated_at = env.ledger().timestamp();
-        Self::save(&env, &shipment);
-        Ok(())
+            env.storage().instance().remove(&DataKey::MigrationCursor);
+            env.storage()
+                .instance()
+                .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+            emit(
+                &env,
+                symbol_short!("migrated"),
+                MigratedEvent {
+                    schema_version: SCHEMA_VERSION,
+                },
+            );
+        }
+        Ok(end)
     }
 
     // ── Queries ───────────────────────────────────────────────────────────
 
+    pub fn get_admin(env: Env) -> Result<Address, ShipmentError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(ShipmentError::NotInitialized)
+    }
+
+    pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
+        ADMIN_TRANSFER.pending(&env)
+    }
+
+    pub fn version(_env: Env) -> u32 {
+        CONTRACT_VERSION
+    }
+
+    /// Storage layout the stored records are known to be in.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(1)
+    }
+
     pub fn get_shipment(env: Env, shipment_id: u64) -> Result<Shipment, ShipmentError> {
         Self::load(&env, shipment_id)
     }
 
+    /// Legs of a multi-leg shipment in order; empty for a single-carrier
+    /// shipment.
+    pub fn get_legs(env: Env, shipment_id: u64) -> Vec<Leg> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Legs(shipment_id))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
     pub fn get_shipments_by_shipper(env: Env, shipper: Address) -> Vec<u64> {
         env.storage()
             .persistent()
@@ -322,6 +1048,24 @@ impl ShipmentContract {
             .unwrap_or_else(|| Vec::new(&env))
     }
 
+    /// Carrier share (basis points) of a partially completed shipment.
+    pub fn get_dispute_ruling(env: Env, shipment_id: u64) -> Option<u32> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Ruling(shipment_id))
+    }
+
+    pub fn get_pauser(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Pauser)
+    }
+
+    pub fn get_pause_state(env: Env) -> PauseState {
+        env.storage()
+            .instance()
+            .get(&DataKey::PauseState)
+            .unwrap_or_default()
+    }
+
     pub fn get_total_shipments(env: Env) -> u64 {
         env.storage()
             .persistent()
@@ -331,11 +1075,50 @@ impl ShipmentContract {
 
     // ── Helpers ───────────────────────────────────────────────────────────
 
+    /// `All` is only in effect once both funding and settlement are paused.
+    fn require_not_paused(env: &Env, scope: PauseScope) -> Result<(), ShipmentError> {
+        let state = Self::get_pause_state(env.clone());
+        let paused = match scope {
+            PauseScope::Funding => state.funding,
+            PauseScope::Settlement => state.settlement,
+            PauseScope::All => state.funding && state.settlement,
+        };
+        if paused {
+            return Err(ShipmentError::Paused);
+        }
+        Ok(())
+    }
+
     fn load(env: &Env, id: u64) -> Result<Shipment, ShipmentError> {
-        env.storage()
+        let key = DataKey::Shipment(id);
+        let fields: Map<Symbol, Val> = env
+            .storage()
             .persistent()
-            .get(&DataKey::Shipment(id))
-            .ok_or(ShipmentError::NotFound)
+            .get(&key)
+            .ok_or(ShipmentError::NotFound)?;
+        if fields.contains_key(symbol_short!("penalty")) {
+            return Ok(env.storage().persistent().get(&key).unwrap());
+        }
+
+        // Schema 1 record: upgrade on read; `migrate` persists it.
+        let old: ShipmentV1 = env.storage().persistent().get(&key).unwrap();
+        Ok(Shipment {
+            id: old.id,
+            shipper: old.shipper,
+            carrier: old.carrier,
+            origin: old.origin,
+            destination: old.destination,
+            cargo_description: old.cargo_description,
+            weight_kg: old.weight_kg,
+            price: old.price,
+            status: old.status,
+            created_at: old.created_at,
+            updated_at: old.updated_at,
+            deliver_by: 0,
+            penalty: LatePenalty::default(),
+            picked_up_at: 0,
+            delivered_at: 0,
+        })
     }
 
     fn save(env: &Env, shipment: &Shipment) {
@@ -349,6 +1132,79 @@ impl ShipmentContract {
         );
     }
 
+    /// Move `shipment` to `to`, persist it and publish the `status` event.
+    fn transition(env: &Env, shipment: &mut Shipment, to: ShipmentStatus, actor: Address) {
+        let from = shipment.status.clone();
+        shipment.status = to.clone();
+        shipment.updated_at = env.ledger().timestamp();
+        if to == ShipmentStatus::InTransit {
+            shipment.picked_up_at = shipment.updated_at;
+        }
+        Self::save(env, shipment);
+
+        emit(
+            env,
+            symbol_short!("status"),
+            StatusEvent {
+                shipment_id: shipment.id,
+                from,
+                to,
+                actor,
+            },
+        );
+    }
+
+    /// Multi-leg shipments only move through the per-leg entry points.
+    fn require_single_leg(env: &Env, shipment_id: u64) -> Result<(), ShipmentError> {
+        if env.storage().persistent().has(&DataKey::Legs(shipment_id)) {
+            return Err(ShipmentError::MultiLeg);
+        }
+        Ok(())
+    }
+
+    fn load_legs(env: &Env, shipment_id: u64) -> Result<Vec<Leg>, ShipmentError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Legs(shipment_id))
+            .ok_or(ShipmentError::LegNotFound)
+    }
+
+    fn save_legs(env: &Env, shipment_id: u64, legs: &Vec<Leg>) {
+        let key = DataKey::Legs(shipment_id);
+        env.storage().persistent().set(&key, legs);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_LEDGERS, TTL_LEDGERS);
+    }
+
+    /// Move leg `index` to `to`, persist the legs and publish the `leg` event.
+    fn transition_leg(
+        env: &Env,
+        shipment_id: u64,
+        legs: &mut Vec<Leg>,
+        index: u32,
+        to: ShipmentStatus,
+        actor: Address,
+    ) {
+        let mut leg = legs.get(index).unwrap();
+        let from = leg.status.clone();
+        leg.status = to.clone();
+        legs.set(index, leg);
+        Self::save_legs(env, shipment_id, legs);
+
+        emit(
+            env,
+            symbol_short!("leg"),
+            LegEvent {
+                shipment_id,
+                index,
+                from,
+                to,
+                actor,
+            },
+        );
+    }
+
     fn next_id(env: &Env) -> u64 {
         let current: u64 = env
             .storage()
@@ -381,7 +1237,10 @@ impl ShipmentContract {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::{testutils::Address as _, Env, String};
+    use soroban_sdk::{
+        testutils::{Address as _, Events, Ledger},
+        vec, BytesN, Env, String,
+    };
 
     fn setup() -> (Env, Address, ShipmentContractClient<'static>) {
         let env = Env::default();
@@ -405,6 +1264,8 @@ mod tests {
             &str(env, "Electronics — 50 units"),
             &120,
             &5_000_000_000i128, // 500 XLM
+            &0,
+            &LatePenalty::default(),
         )
     }
 
@@ -524,6 +1385,30 @@ mod tests {
         assert_eq!(client.get_shipment(&id).status, ShipmentStatus::Completed);
     }
 
+    #[test]
+    fn test_dispute_resolved_as_partial() {
+        let (env, _admin, client) = setup();
+        let shipper = Address::generate(&env);
+        let carrier = Address::generate(&env);
+
+        let id = make_shipment(&env, &client, &shipper);
+        client.accept_shipment(&carrier, &id);
+        client.mark_in_transit(&carrier, &id);
+        client.raise_dispute(&carrier, &id);
+
+        assert_eq!(
+            client.try_resolve_dispute_partial(&id, &10_000),
+            Err(Ok(ShipmentError::InvalidInput))
+        );
+
+        client.resolve_dispute_partial(&id, &6_000);
+        assert_eq!(
+            client.get_shipment(&id).status,
+            ShipmentStatus::PartiallyCompleted
+        );
+        assert_eq!(client.get_dispute_ruling(&id), Some(6_000));
+    }
+
     #[test]
     fn test_dispute_resolved_as_cancelled() {
         let (env, _admin, client) = setup();
@@ -563,6 +1448,55 @@ mod tests {
         assert_eq!(result, Err(Ok(ShipmentError::NotFound)));
     }
 
+    #[test]
+    fn test_delivery_promise_recorded() {
+        let (env, _, client) = setup();
+        let shipper = Address::generate(&env);
+        let carrier = Address::generate(&env);
+        env.ledger().with_mut(|l| l.timestamp = 1_000);
+
+        let penalty = LatePenalty {
+            bps_per_hour: 50,
+            max_bps: 2_000,
+        };
+        let id = client.create_shipment(
+            &shipper,
+            &str(&env, "A"),
+            &str(&env, "B"),
+            &str(&env, "cargo"),
+            &1u32,
+            &1_000i128,
+            &86_400,
+            &penalty,
+        );
+        client.accept_shipment(&carrier, &id);
+        env.ledger().with_mut(|l| l.timestamp = 3_600);
+        client.mark_in_transit(&carrier, &id);
+        env.ledger().with_mut(|l| l.timestamp = 90_000);
+        client.mark_delivered(&carrier, &id);
+
+        let s = client.get_shipment(&id);
+        assert_eq!(s.deliver_by, 86_400);
+        assert_eq!(s.penalty, penalty);
+        assert_eq!(s.picked_up_at, 3_600);
+        assert_eq!(s.delivered_at, 90_000);
+
+        // A promise already in the past is rejected.
+        assert_eq!(
+            client.try_create_shipment(
+                &shipper,
+                &str(&env, "A"),
+                &str(&env, "B"),
+                &str(&env, "cargo"),
+                &1u32,
+                &1_000i128,
+                &90_000,
+                &penalty,
+            ),
+            Err(Ok(ShipmentError::InvalidInput))
+        );
+    }
+
     #[test]
     fn test_invalid_input_zero_weight() {
         let (env, _, client) = setup();
@@ -575,7 +1509,485 @@ mod tests {
             &str(&env, "cargo"),
             &0u32,
             &1_000i128,
+            &0,
+            &LatePenalty::default(),
         );
         assert_eq!(result, Err(Ok(ShipmentError::InvalidInput)));
     }
+
+    fn status_event(
+        env: &Env,
+        client: &ShipmentContractClient,
+        shipment_id: u64,
+        from: ShipmentStatus,
+        to: ShipmentStatus,
+        actor: &Address,
+    ) -> (Address, Vec<Val>, Val) {
+        (
+            client.address.clone(),
+            (EVENT_NS, symbol_short!("status"), EVENT_VERSION).into_val(env),
+            StatusEvent {
+                shipment_id,
+                from,
+                to,
+                actor: actor.clone(),
+            }
+            .into_val(env),
+        )
+    }
+
+    #[test]
+    fn test_create_emits_event() {
+        let (env, _, client) = setup();
+        let shipper = Address::generate(&env);
+
+        let id = make_shipment(&env, &client, &shipper);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (EVENT_NS, symbol_short!("created"), EVENT_VERSION).into_val(&env),
+                    CreatedEvent {
+                        shipment_id: id,
+                        shipper: shipper.clone(),
+                        price: 5_000_000_000,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_transition_emits_status_event() {
+        let (env, _, client) = setup();
+        let shipper = Address::generate(&env);
+        let carrier = Address::generate(&env);
+        let id = make_shipment(&env, &client, &shipper);
+
+        client.accept_shipment(&carrier, &id);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                status_event(
+                    &env,
+                    &client,
+                    id,
+                    ShipmentStatus::Created,
+                    ShipmentStatus::Accepted,
+                    &carrier,
+                ),
+            ]
+        );
+
+        client.mark_in_transit(&carrier, &id);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                status_event(
+                    &env,
+                    &client,
+                    id,
+                    ShipmentStatus::Accepted,
+                    ShipmentStatus::InTransit,
+                    &carrier,
+                ),
+            ]
+        );
+
+        client.mark_delivered(&carrier, &id);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                status_event(
+                    &env,
+                    &client,
+                    id,
+                    ShipmentStatus::InTransit,
+                    ShipmentStatus::Delivered,
+                    &carrier,
+                ),
+            ]
+        );
+
+        client.confirm_delivery(&shipper, &id);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                status_event(
+                    &env,
+                    &client,
+                    id,
+                    ShipmentStatus::Delivered,
+                    ShipmentStatus::Completed,
+                    &shipper,
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancel_and_dispute_emit_status_events() {
+        let (env, admin, client) = setup();
+        let shipper = Address::generate(&env);
+        let carrier = Address::generate(&env);
+
+        let cancelled = make_shipment(&env, &client, &shipper);
+        client.cancel_shipment(&shipper, &cancelled);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                status_event(
+                    &env,
+                    &client,
+                    cancelled,
+                    ShipmentStatus::Created,
+                    ShipmentStatus::Cancelled,
+                    &shipper,
+                ),
+            ]
+        );
+
+        let id = make_shipment(&env, &client, &shipper);
+        client.accept_shipment(&carrier, &id);
+        client.mark_in_transit(&carrier, &id);
+        client.raise_dispute(&shipper, &id);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                status_event(
+                    &env,
+                    &client,
+                    id,
+                    ShipmentStatus::InTransit,
+                    ShipmentStatus::Disputed,
+                    &shipper,
+                ),
+            ]
+        );
+
+        client.resolve_dispute(&id, &true);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                status_event(
+                    &env,
+                    &client,
+                    id,
+                    ShipmentStatus::Disputed,
+                    ShipmentStatus::Completed,
+                    &admin,
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause_funding_blocks_new_postings() {
+        let (env, _, client) = setup();
+        let pauser = Address::generate(&env);
+        let shipper = Address::generate(&env);
+        let carrier = Address::generate(&env);
+        client.set_pauser(&pauser);
+
+        let id = make_shipment(&env, &client, &shipper);
+        client.pause(&pauser, &PauseScope::Funding);
+
+        assert_eq!(
+            client.try_create_shipment(
+                &shipper,
+                &str(&env, "A"),
+                &str(&env, "B"),
+                &str(&env, "cargo"),
+                &1u32,
+                &1_000i128,
+                &0,
+                &LatePenalty::default(),
+            ),
+            Err(Ok(ShipmentError::Paused))
+        );
+        assert_eq!(
+            client.try_accept_shipment(&carrier, &id),
+            Err(Ok(ShipmentError::Paused))
+        );
+
+        // Cancellation is a settlement action, still open.
+        client.cancel_shipment(&shipper, &id);
+        assert_eq!(client.get_shipment(&id).status, ShipmentStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_pause_all_then_admin_unpause() {
+        let (env, _, client) = setup();
+        let pauser = Address::generate(&env);
+        let shipper = Address::generate(&env);
+        let carrier = Address::generate(&env);
+        client.set_pauser(&pauser);
+
+        let id = make_shipment(&env, &client, &shipper);
+        client.accept_shipment(&carrier, &id);
+
+        client.pause(&pauser, &PauseScope::All);
+        assert_eq!(
+            client.try_mark_in_transit(&carrier, &id),
+            Err(Ok(ShipmentError::Paused))
+        );
+        assert_eq!(
+            client.try_cancel_shipment(&shipper, &id),
+            Err(Ok(ShipmentError::Paused))
+        );
+        // Reads keep working.
+        assert_eq!(client.get_shipment(&id).status, ShipmentStatus::Accepted);
+
+        assert_eq!(
+            client.try_pause(&Address::generate(&env), &PauseScope::All),
+            Err(Ok(ShipmentError::Unauthorized))
+        );
+
+        client.unpause(&PauseScope::Settlement);
+        assert_eq!(
+            client.get_pause_state(),
+            PauseState {
+                funding: true,
+                settlement: false
+            }
+        );
+        // Partially unpaused — in-flight transitions resume.
+        client.mark_in_transit(&carrier, &id);
+
+        client.unpause(&PauseScope::All);
+        assert_eq!(client.get_pause_state(), PauseState::default());
+    }
+
+    #[test]
+    fn test_upgrade_requires_admin() {
+        let (env, _, client) = setup();
+        assert_eq!(client.version(), CONTRACT_VERSION);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+
+        env.mock_auths(&[]);
+        assert!(client
+            .try_upgrade(&BytesN::from_array(&env, &[0; 32]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_schema_1_shipments_upgraded_on_read_and_migrated() {
+        let (env, _, client) = setup();
+        let shipper = Address::generate(&env);
+        let id = make_shipment(&env, &client, &shipper);
+
+        // Simulate a deployment that predates delivery promises.
+        env.as_contract(&client.address, || {
+            let old = ShipmentV1 {
+                id,
+                shipper: shipper.clone(),
+                carrier: None,
+                origin: str(&env, "Lagos"),
+                destination: str(&env, "Accra"),
+                cargo_description: str(&env, "Textiles"),
+                weight_kg: 40,
+                price: 1_000,
+                status: ShipmentStatus::Created,
+                created_at: 0,
+                updated_at: 0,
+            };
+            env.storage().persistent().set(&DataKey::Shipment(id), &old);
+            env.storage().instance().remove(&DataKey::SchemaVersion);
+        });
+        assert_eq!(client.schema_version(), 1);
+
+        let s = client.get_shipment(&id);
+        assert_eq!(s.origin, str(&env, "Lagos"));
+        assert_eq!(s.deliver_by, 0);
+
+        // Writes go out in the new layout even before `migrate` runs.
+        let carrier = Address::generate(&env);
+        client.accept_shipment(&carrier, &id);
+        assert_eq!(client.get_shipment(&id).carrier, Some(carrier));
+
+        make_shipment(&env, &client, &shipper);
+        make_shipment(&env, &client, &shipper);
+        // Batches cannot skip ahead to the end.
+        assert_eq!(
+            client.try_migrate(&3, &10),
+            Err(Ok(ShipmentError::MigrationOutOfOrder))
+        );
+        assert_eq!(client.migrate(&1, &1), 2);
+        assert_eq!(client.schema_version(), 1);
+        assert_eq!(
+            client.try_migrate(&1, &1),
+            Err(Ok(ShipmentError::MigrationOutOfOrder))
+        );
+        assert_eq!(client.migrate(&2, &1), 3);
+        assert_eq!(client.schema_version(), 1);
+        assert_eq!(client.migrate(&3, &10), 4);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+    }
+
+    fn make_multileg(env: &Env, client: &ShipmentContractClient, shipper: &Address) -> u64 {
+        client.create_multileg_shipment(
+            shipper,
+            &str(env, "Lagos, Nigeria"),
+            &str(env, "Nairobi, Kenya"),
+            &str(env, "Electronics — 50 units"),
+            &120,
+            &vec![env, 3_000_000_000i128, 2_000_000_000i128],
+            &0,
+            &LatePenalty::default(),
+        )
+    }
+
+    #[test]
+    fn test_multileg_shipment_follows_its_legs() {
+        let (env, _, client) = setup();
+        let shipper = Address::generate(&env);
+        let first = Address::generate(&env);
+        let second = Address::generate(&env);
+        let id = make_multileg(&env, &client, &shipper);
+
+        let s = client.get_shipment(&id);
+        assert_eq!(s.price, 5_000_000_000);
+        assert_eq!(s.status, ShipmentStatus::Created);
+        let legs = client.get_legs(&id);
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs.get(1).unwrap().price, 2_000_000_000);
+
+        // Legs are accepted in order.
+        assert_eq!(
+            client.try_accept_leg(&second, &id, &1),
+            Err(Ok(ShipmentError::InvalidStatus))
+        );
+        client.accept_leg(&first, &id, &0);
+        let s = client.get_shipment(&id);
+        assert_eq!(s.status, ShipmentStatus::Accepted);
+        assert_eq!(s.carrier, Some(first.clone()));
+        client.accept_leg(&second, &id, &1);
+
+        // The second carrier waits for the handoff at the hub.
+        let result = client.try_mark_leg_in_transit(&second, &id, &1);
+        assert_eq!(result, Err(Ok(ShipmentError::InvalidStatus)));
+
+        env.ledger().with_mut(|l| l.timestamp = 500);
+        client.mark_leg_in_transit(&first, &id, &0);
+        assert_eq!(client.get_shipment(&id).status, ShipmentStatus::InTransit);
+        env.ledger().with_mut(|l| l.timestamp = 1_000);
+        client.mark_leg_delivered(&first, &id, &0);
+        client.mark_leg_in_transit(&second, &id, &1);
+        client.confirm_leg(&shipper, &id, &0);
+        let s = client.get_shipment(&id);
+        assert_eq!(s.status, ShipmentStatus::InTransit);
+        // The shipment went in transit with the first leg's pickup.
+        assert_eq!(s.picked_up_at, 500);
+        assert_eq!(s.delivered_at, 0);
+        let leg = client.get_legs(&id).get(0).unwrap();
+        assert_eq!(leg.status, ShipmentStatus::Completed);
+        assert_eq!(leg.delivered_at, 1_000);
+
+        env.ledger().with_mut(|l| l.timestamp = 2_000);
+        client.mark_leg_delivered(&second, &id, &1);
+        let s = client.get_shipment(&id);
+        assert_eq!(s.status, ShipmentStatus::Delivered);
+        assert_eq!(s.delivered_at, 2_000);
+
+        client.confirm_leg(&shipper, &id, &1);
+        assert_eq!(client.get_shipment(&id).status, ShipmentStatus::Completed);
+        assert_eq!(client.get_shipments_by_carrier(&second), vec![&env, id]);
+    }
+
+    #[test]
+    fn test_leg_transition_emits_leg_event() {
+        let (env, _, client) = setup();
+        let shipper = Address::generate(&env);
+        let carrier = Address::generate(&env);
+        let id = make_multileg(&env, &client, &shipper);
+
+        client.accept_leg(&carrier, &id, &0);
+        client.accept_leg(&carrier, &id, &1);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (EVENT_NS, symbol_short!("leg"), EVENT_VERSION).into_val(&env),
+                    LegEvent {
+                        shipment_id: id,
+                        index: 1,
+                        from: ShipmentStatus::Created,
+                        to: ShipmentStatus::Accepted,
+                        actor: carrier,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multileg_guards() {
+        let (env, _, client) = setup();
+        let shipper = Address::generate(&env);
+        let first = Address::generate(&env);
+        let second = Address::generate(&env);
+
+        let one_leg = client.try_create_multileg_shipment(
+            &shipper,
+            &str(&env, "Lagos, Nigeria"),
+            &str(&env, "Nairobi, Kenya"),
+            &str(&env, "Electronics — 50 units"),
+            &120,
+            &vec![&env, 5_000_000_000i128],
+            &0,
+            &LatePenalty::default(),
+        );
+        assert_eq!(one_leg, Err(Ok(ShipmentError::InvalidInput)));
+
+        let id = make_multileg(&env, &client, &shipper);
+        assert_eq!(
+            client.try_accept_shipment(&first, &id),
+            Err(Ok(ShipmentError::MultiLeg))
+        );
+        assert_eq!(
+            client.try_accept_leg(&first, &id, &2),
+            Err(Ok(ShipmentError::LegNotFound))
+        );
+
+        client.accept_leg(&first, &id, &0);
+        client.accept_leg(&second, &id, &1);
+        assert_eq!(
+            client.try_mark_leg_in_transit(&second, &id, &0),
+            Err(Ok(ShipmentError::NotCarrier))
+        );
+        client.mark_leg_in_transit(&first, &id, &0);
+        client.mark_leg_delivered(&first, &id, &0);
+        client.mark_leg_in_transit(&second, &id, &1);
+        client.mark_leg_delivered(&second, &id, &1);
+
+        // Legs are confirmed in order.
+        assert_eq!(
+            client.try_confirm_leg(&shipper, &id, &1),
+            Err(Ok(ShipmentError::InvalidStatus))
+        );
+        assert_eq!(
+            client.try_confirm_delivery(&shipper, &id),
+            Err(Ok(ShipmentError::MultiLeg))
+        );
+
+        // Any leg's carrier may dispute; that freezes the remaining legs.
+        client.raise_dispute(&second, &id);
+        assert_eq!(
+            client.try_confirm_leg(&shipper, &id, &0),
+            Err(Ok(ShipmentError::InvalidStatus))
+        );
+    }
 }